@@ -0,0 +1,151 @@
+//! Timestamped copies of the tasks in `backups/` of the store. A copy is
+//! taken after every change except undo and redo, and old ones are thinned
+//! out by the retention policy in the config.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::config::{get_config, BackupSettings};
+use crate::files::{get_dir, tasks_file, Task};
+
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub id: String,
+    pub time: NaiveDateTime,
+    pub path: PathBuf,
+}
+
+fn backup_dir() -> PathBuf {
+    let mut dir = get_dir();
+    dir.push("backups");
+    dir
+}
+
+/// Copies the tasks file to the backup directory, prunes old copies and
+/// returns the id of the new one. A second copy within the same second
+/// replaces the first.
+pub fn create() -> Result<String, String> {
+    let dir = backup_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let now = Local::now().naive_local();
+    let id = now.format(ID_FORMAT).to_string();
+    std::fs::copy(tasks_file(), dir.join(format!("tasks-{}.json", id))).map_err(|e| e.to_string())?;
+
+    let settings = get_config().map(|config| config.backup).unwrap_or_default();
+    let backups = list();
+    for (backup, keep) in backups.iter().zip(retain(&backups, &settings, now.date())) {
+        if !keep {
+            std::fs::remove_file(&backup.path).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(id)
+}
+
+/// All backups, newest first.
+pub fn list() -> Vec<Backup> {
+    let entries = match std::fs::read_dir(backup_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut backups = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let id = name.strip_prefix("tasks-")?.strip_suffix(".json")?.to_string();
+            let time = NaiveDateTime::parse_from_str(&id, ID_FORMAT).ok()?;
+            Some(Backup { id, time, path: entry.path() })
+        })
+        .collect::<Vec<_>>();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.time));
+    backups
+}
+
+pub fn read(id: &str) -> Result<HashMap<String, Vec<Task>>, String> {
+    let backup = list()
+        .into_iter()
+        .find(|backup| backup.id == id)
+        .ok_or_else(|| format!("Backup not found: {}", id))?;
+
+    let content = std::fs::read_to_string(&backup.path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| format!("Broken backup {}: {}", id, e))
+}
+
+/// Which of the backups (newest first) to keep: the `last` newest ones, and
+/// the newest one of each of the `daily` days and the `weekly` ISO weeks
+/// up to and including `today`.
+pub fn retain(backups: &[Backup], settings: &BackupSettings, today: NaiveDate) -> Vec<bool> {
+    let mut keep = vec![false; backups.len()];
+
+    keep.iter_mut().take(settings.last).for_each(|keep| *keep = true);
+
+    let this_week = today.week(Weekday::Mon).first_day();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for (i, backup) in backups.iter().enumerate() {
+        let date = backup.time.date();
+        let day = (today - date).num_days();
+        if (0..settings.daily as i64).contains(&day) && days.insert(date) {
+            keep[i] = true;
+        }
+        let week = (this_week - date.week(Weekday::Mon).first_day()).num_weeks();
+        if (0..settings.weekly as i64).contains(&week) && weeks.insert(week) {
+            keep[i] = true;
+        }
+    }
+
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backups(times: &[&str]) -> Vec<Backup> {
+        times
+            .iter()
+            .map(|time| Backup {
+                id: time.to_string(),
+                time: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+                path: PathBuf::new(),
+            })
+            .collect()
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn keeps_the_last_backups() {
+        let backups = backups(&["2026-10-19 12:00", "2026-10-19 11:00", "2026-10-19 10:00"]);
+        let settings = BackupSettings { last: 2, daily: 0, weekly: 0 };
+        assert_eq!(retain(&backups, &settings, date("2026-10-19")), [true, true, false]);
+    }
+
+    #[test]
+    fn keeps_the_newest_of_each_recent_day() {
+        // Monday, Sunday (twice), Saturday and the Saturday a week earlier.
+        let backups = backups(&["2026-10-19 09:00", "2026-10-18 20:00", "2026-10-18 08:00", "2026-10-17 08:00", "2026-10-10 08:00"]);
+        let settings = BackupSettings { last: 0, daily: 2, weekly: 0 };
+        assert_eq!(retain(&backups, &settings, date("2026-10-19")), [true, true, false, false, false]);
+
+        // Days without a backup still count towards `daily`.
+        assert_eq!(retain(&backups, &settings, date("2026-10-20")), [true, false, false, false, false]);
+        assert_eq!(retain(&backups, &settings, date("2026-10-25")), [false; 5]);
+    }
+
+    #[test]
+    fn keeps_the_newest_of_each_recent_week() {
+        let backups = backups(&["2026-10-19 09:00", "2026-10-18 20:00", "2026-10-17 08:00", "2026-10-05 08:00", "2026-09-28 08:00"]);
+        let settings = BackupSettings { last: 0, daily: 0, weekly: 3 };
+        assert_eq!(retain(&backups, &settings, date("2026-10-21")), [true, true, false, true, false]);
+    }
+}
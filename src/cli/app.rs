@@ -1,34 +1,147 @@
-use std::{error::Error, process::exit};
+use std::error::Error;
 
-use clap::{arg, command, ArgMatches, Command};
+use clap::{arg, command, ArgAction, ArgMatches, Command, ValueEnum};
+
+use crate::formats::{FileFormat, FormatOptions};
+use crate::search::SearchMode;
+use crate::sort::{GroupBy, SortKey};
+
+use super::OutputFormat;
 
 #[derive(Debug)]
 pub enum Subcommands {
-    Add(Option<String>, Option<String>),
-    Remove(Option<String>, Option<String>),
-    Check(Option<String>, Option<String>),
-    Tasks(Option<String>),
+    Add(Option<String>, Option<String>, TaskOptions),
+    Remove(Option<String>, Option<String>, SelectOptions),
+    Check(Option<String>, Option<String>, SelectOptions),
+    Tasks(Option<String>, TasksOptions),
     AddList(Option<String>),
-    RemoveList(Option<String>),
+    RemoveList(Option<String>, bool),
     Lists,
+    Search(Option<String>, SearchMode, bool),
+    Preview(Option<String>),
+    Move(Option<String>, Option<String>),
+    Clean(Option<String>),
+    ArchiveList(Option<String>),
+    ArchiveRestore(Option<String>, Option<String>),
+    Undo(usize),
+    Redo(usize),
+    Log(LogOptions),
+    Trash,
+    TrashRestore(Option<String>),
+    TrashEmpty,
+    BackupNow,
+    BackupList,
+    BackupRestore(Option<String>),
+    Import(FileFormat, Option<String>, Option<String>, FormatOptions),
+    Export(FileFormat, Option<String>, Option<String>, FormatOptions),
+    Scan(Option<String>, Option<String>),
+}
+
+#[derive(Debug, Default)]
+pub struct TaskOptions {
+    pub due: Option<String>,
+    pub tags: Vec<String>,
+    pub priority: Option<String>,
+    /// Read task names from stdin, one per line.
+    pub stdin: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct SelectOptions {
+    pub exact: bool,
+    /// Read task names or positions from stdin, one per line.
+    pub stdin: bool,
+    /// Apply to every task matching the filter query.
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct TasksOptions {
+    pub filter: Option<String>,
+    pub sort: Option<SortKey>,
+    pub group_by: Option<GroupBy>,
+    pub table: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct LogOptions {
+    pub list: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Interaction {
+    /// Ask on the terminal.
+    #[default]
+    Ask,
+    /// Answer yes to every question.
+    Yes,
+    /// Answer no to every question.
+    No,
+    /// Never ask, take the default answer.
+    NonInteractive,
+}
+
+#[derive(Debug, Default)]
+pub struct GlobalOptions {
+    pub format: Option<OutputFormat>,
+    pub interaction: Interaction,
+    /// Use `~/.todo-app` even inside a project.
+    pub global: bool,
 }
 
 #[derive(Debug)]
 pub struct Config {
     pub subcommand: Option<Subcommands>,
+    pub options: GlobalOptions,
 }
 
 pub fn get_args() -> Result<Config, Box<dyn Error>> {
     //TODO: read config from file(default list)
     let command = command!()
         .arg_required_else_help(true) //TODO: delete
+        .arg(
+            arg!(format: --format <FORMAT> "Output format of tasks, lists, search and archive list")
+                .value_parser(clap::value_parser!(OutputFormat))
+                .global(true)
+        )
+        .arg(
+            arg!(yes: -y --yes "Answer yes to all questions")
+                .conflicts_with_all(["no", "non_interactive"])
+                .global(true)
+        )
+        .arg(
+            arg!(no: --no "Answer no to all questions")
+                .conflicts_with("non_interactive")
+                .global(true)
+        )
+        .arg(
+            arg!(non_interactive: --"non-interactive" "Never prompt, use default answers")
+                .global(true)
+        )
+        .arg(
+            arg!(global: --global "Use the tasks in ~/.todo-app, not the project's")
+                .global(true)
+        )
         .subcommand(
             Command::new("add")
-                .arg(arg!(task_name: <TASK> "Task name"))
+                .arg(
+                    arg!(task_name: <TASK> "Task name")
+                        .required(false)
+                        .required_unless_present("stdin")
+                )
                 .arg(
                     arg!(list_name: <LIST> "List name")
                         .required(false)
                 )
+                .arg(arg!(due: --due <DATE> "Due date (YYYY-MM-DD, today, tomorrow)"))
+                .arg(
+                    arg!(tag: -t --tag <TAG> "Tag, can be repeated")
+                        .action(ArgAction::Append)
+                )
+                .arg(arg!(priority: -p --priority <PRIORITY> "Priority from A (highest) to Z"))
+                .arg(stdin_arg())
                 .about("Add a new task"),
         )
         .subcommand(
@@ -41,6 +154,9 @@ pub fn get_args() -> Result<Config, Box<dyn Error>> {
                     arg!(list_name: <LIST> "List name")
                         .required(false)
                 )
+                .arg(arg!(exact: --exact "Match the task name exactly"))
+                .arg(stdin_lookup_arg())
+                .arg(where_arg())
                 .about("Remove a task"),
         )
         .subcommand(
@@ -50,11 +166,30 @@ pub fn get_args() -> Result<Config, Box<dyn Error>> {
                     arg!(list_name: <LIST> "List name")
                         .required(false)
                 )
+                .arg(arg!(exact: --exact "Match the task name exactly"))
+                .arg(stdin_lookup_arg())
+                .arg(where_arg())
                 .about("Check/uncheck task"),
         )
         .subcommand(
             Command::new("tasks")
                 .arg(arg!(list_name: <LIST> "List name").required(false))
+                .arg(
+                    arg!(filter: -w --where <FILTER> "Filter query, e.g. 'status:open and (tag:work or due<today)'")
+                )
+                .arg(arg!(view: --view <VIEW> "Saved filter query from the config"))
+                .arg(
+                    arg!(sort: -s --sort <KEY> "Sort tasks")
+                        .value_parser(clap::value_parser!(SortKey))
+                )
+                .arg(
+                    arg!(group_by: -g --"group-by" <GROUP> "Group tasks (default: list)")
+                        .value_parser(clap::value_parser!(GroupBy))
+                )
+                .arg(
+                    arg!(table: --table "Print tasks as an aligned table")
+                        .conflicts_with("group_by")
+                )
                 .about("Print all tasks"),
         )
         .subcommand(
@@ -65,61 +200,363 @@ pub fn get_args() -> Result<Config, Box<dyn Error>> {
         .subcommand(
             Command::new("remove-list")
                 .arg(arg!(list_name: <LIST> "List name"))
+                .arg(arg!(force: -f --force "Don't ask, even for a non-empty or the default list"))
                 .about("Delete the list"),
         )
-        .subcommand(Command::new("lists").about("Print all lists"));
+        .subcommand(Command::new("lists").about("Print all lists"))
+        .subcommand(
+            Command::new("search")
+                .arg(arg!(pattern: <PATTERN> "Text or regular expression to search for"))
+                .arg(arg!(ignore_case: -i --"ignore-case" "Case-insensitive matching"))
+                .arg(arg!(regex: -e --regex "Treat the pattern as a regular expression"))
+                .about("Search tasks in all lists"),
+        )
+        .subcommand(
+            Command::new("move")
+                .arg(arg!(list_name: <LIST> "List to move the tasks to"))
+                .arg(
+                    arg!(filter: -w --where <FILTER> "Filter query selecting the tasks")
+                        .required(true)
+                )
+                .about("Move tasks to another list"),
+        )
+        .subcommand(
+            Command::new("clean")
+                .arg(arg!(list_name: <LIST> "List name (default: all lists)").required(false))
+                .about("Move checked tasks to the archive"),
+        )
+        .subcommand(
+            Command::new("archive")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list")
+                        .arg(arg!(list_name: <LIST> "List the tasks were archived from").required(false))
+                        .about("Print archived tasks"),
+                )
+                .subcommand(
+                    Command::new("restore")
+                        .arg(arg!(task_name: <TASK> "Task name"))
+                        .arg(arg!(list_name: <LIST> "List the task was archived from").required(false))
+                        .about("Put an archived task back into its list"),
+                )
+                .about("Browse and restore archived tasks"),
+        )
+        .subcommand(
+            Command::new("undo")
+                .arg(count_arg())
+                .about("Revert the last changes"),
+        )
+        .subcommand(
+            Command::new("redo")
+                .arg(count_arg())
+                .about("Apply reverted changes again"),
+        )
+        .subcommand(
+            Command::new("log")
+                .arg(arg!(list: -l --list <LIST> "Only show changes to this list"))
+                .arg(arg!(since: --since <DATE> "Only show changes on or after this date"))
+                .arg(arg!(until: --until <DATE> "Only show changes on or before this date"))
+                .about("Print the history of changes"),
+        )
+        .subcommand(
+            Command::new("trash")
+                .subcommand(
+                    Command::new("restore")
+                        .arg(arg!(item: <ITEM> "List name, task name or 'list: task'"))
+                        .about("Put a removed task or list back"),
+                )
+                .subcommand(Command::new("empty").about("Delete everything in the trash for good"))
+                .about("Print removed tasks and lists"),
+        )
+        .subcommand(
+            Command::new("backup")
+                .subcommand_required(true)
+                .subcommand(Command::new("now").about("Back up the tasks right away"))
+                .subcommand(Command::new("list").about("Print all backups, newest first"))
+                .subcommand(
+                    Command::new("restore")
+                        .arg(arg!(id: <ID> "Backup id as printed by 'backup list'"))
+                        .about("Replace all lists with the ones in a backup"),
+                )
+                .about("Manage backups of the tasks"),
+        )
+        .subcommand(
+            Command::new("import")
+                .arg(format_arg())
+                .arg(arg!(file: <FILE> "File to read (default: stdin)").required(false))
+                .arg(arg!(list: -l --list <LIST> "List for tasks that don't name one (default: default list)"))
+                .arg(columns_arg())
+                .about("Add tasks from another tool's file"),
+        )
+        .subcommand(
+            Command::new("export")
+                .arg(format_arg())
+                .arg(arg!(list_name: <LIST> "Only export this list").required(false))
+                .arg(arg!(output: -o --output <FILE> "File to write (default: stdout)"))
+                .arg(columns_arg())
+                .about("Write tasks in another tool's format"),
+        )
+        .subcommand(
+            Command::new("scan")
+                .arg(arg!(path: <PATH> "Directory to scan (default: current directory)").required(false))
+                .arg(arg!(list: -l --list <LIST> "List to sync (default: the directory's name)"))
+                .about("Sync TODO, FIXME and XXX comments in source code into a list"),
+        )
+        .subcommand(
+            Command::new("preview")
+                .arg(arg!(item: <ITEM> "List name, task name or 'list: task'"))
+                .about("Print details of a picker item")
+                .hide(true),
+        );
 
     let matches = command.get_matches();
-    
-    let subcommand = matches.subcommand();
-
-    match subcommand {
-        Some((subcmd, args)) => match subcmd {
-            "add" => Ok(Config {
-                subcommand: Some(Subcommands::Add(
-                    get_string("task_name", args),
-                    get_string("list_name", args),
-                )),
-            }),
-            "remove" => Ok(Config {
-                subcommand: Some(Subcommands::Remove(
-                    get_string("task_name", args),
-                    get_string("list_name", args),
-                )),
-            }),
-            "check" => Ok(Config {
-                subcommand: Some(Subcommands::Check(
-                    get_string("task_name", args),
-                    get_string("list_name", args),
-                )),
-            }),
-            "tasks" => Ok(Config {
-                subcommand: Some(Subcommands::Tasks(get_string("list_name", args))),
-            }),
-            "add-list" => Ok(Config {
-                subcommand: Some(Subcommands::AddList(get_string("list_name", args))),
-            }),
-            "remove-list" => Ok(Config {
-                subcommand: Some(Subcommands::RemoveList(get_string("list_name", args))),
-            }),
-            "lists" => Ok(Config {
-                subcommand: Some(Subcommands::Lists),
-            }),
-            _ => Err("Wrong subcommand".into()),
+
+    let (subcmd, args) = match matches.subcommand() {
+        Some(subcommand) => subcommand,
+        None => return Ok(Config { subcommand: None, options: GlobalOptions::default() }),
+    };
+
+    let subcommand = match subcmd {
+        "add" => {
+            let (task, list) = get_task_and_list(args)?;
+            Subcommands::Add(task, list, TaskOptions {
+                due: get_string("due", args)?,
+                tags: get_strings("tag", args),
+                priority: get_string("priority", args)?,
+                stdin: args.get_flag("stdin"),
+            })
+        }
+        "remove" => {
+            let (task, list) = get_task_and_list(args)?;
+            Subcommands::Remove(task, list, get_select_options(args)?)
+        }
+        "check" => {
+            let (task, list) = get_task_and_list(args)?;
+            Subcommands::Check(task, list, get_select_options(args)?)
+        }
+        "tasks" => Subcommands::Tasks(
+            get_string("list_name", args)?,
+            TasksOptions {
+                filter: get_tasks_filter(args)?,
+                sort: args.get_one::<SortKey>("sort").copied(),
+                group_by: args.get_one::<GroupBy>("group_by").copied(),
+                table: args.get_flag("table"),
+            },
+        ),
+        "add-list" => Subcommands::AddList(get_string("list_name", args)?),
+        "remove-list" => Subcommands::RemoveList(get_string("list_name", args)?, args.get_flag("force")),
+        "lists" => Subcommands::Lists,
+        "search" => Subcommands::Search(
+            get_string("pattern", args)?,
+            if args.get_flag("regex") { SearchMode::Regex } else { SearchMode::Substring },
+            args.get_flag("ignore_case"),
+        ),
+        "preview" => Subcommands::Preview(get_string("item", args)?),
+        "move" => Subcommands::Move(get_string("list_name", args)?, get_string("filter", args)?),
+        "clean" => Subcommands::Clean(get_string("list_name", args)?),
+        "archive" => match args.subcommand() {
+            Some(("list", args)) => Subcommands::ArchiveList(get_string("list_name", args)?),
+            Some(("restore", args)) => Subcommands::ArchiveRestore(
+                get_string("task_name", args)?,
+                get_string("list_name", args)?,
+            ),
+            _ => return Err("Wrong subcommand".into()),
         },
-        None => Ok(Config { subcommand: None }),
+        "undo" => Subcommands::Undo(*args.get_one::<u64>("count").unwrap() as usize),
+        "redo" => Subcommands::Redo(*args.get_one::<u64>("count").unwrap() as usize),
+        "trash" => match args.subcommand() {
+            None => Subcommands::Trash,
+            Some(("restore", args)) => Subcommands::TrashRestore(get_string("item", args)?),
+            Some(("empty", _)) => Subcommands::TrashEmpty,
+            _ => return Err("Wrong subcommand".into()),
+        },
+        "backup" => match args.subcommand() {
+            Some(("now", _)) => Subcommands::BackupNow,
+            Some(("list", _)) => Subcommands::BackupList,
+            Some(("restore", args)) => Subcommands::BackupRestore(get_string("id", args)?),
+            _ => return Err("Wrong subcommand".into()),
+        },
+        "import" => {
+            let (format, file) = get_import_format(args)?;
+            Subcommands::Import(format, file, get_string("list", args)?, get_format_options(args)?)
+        }
+        "export" => {
+            let (format, list) = get_export_format(args)?;
+            Subcommands::Export(format, list, get_string("output", args)?, get_format_options(args)?)
+        }
+        "scan" => Subcommands::Scan(get_string("path", args)?, get_string("list", args)?),
+        "log" => Subcommands::Log(LogOptions {
+            list: get_string("list", args)?,
+            since: get_string("since", args)?,
+            until: get_string("until", args)?,
+        }),
+        _ => return Err("Wrong subcommand".into()),
+    };
+
+    let format = args.get_one::<OutputFormat>("format").copied();
+    let supports_format = matches!(
+        subcommand,
+        Subcommands::Tasks(..) | Subcommands::Lists | Subcommands::Search(..) | Subcommands::ArchiveList(..) | Subcommands::Export(..)
+    );
+    if format.is_some() && !supports_format {
+        return Err(format!("--format is not supported by '{}'", subcmd).into());
     }
+
+    Ok(Config {
+        subcommand: Some(subcommand),
+        options: GlobalOptions {
+            format,
+            interaction: if args.get_flag("yes") {
+                Interaction::Yes
+            } else if args.get_flag("no") {
+                Interaction::No
+            } else if args.get_flag("non_interactive") {
+                Interaction::NonInteractive
+            } else {
+                Interaction::Ask
+            },
+            global: args.get_flag("global"),
+        },
+    })
+}
+
+fn stdin_arg() -> clap::Arg {
+    arg!(stdin: --stdin "Read task names from stdin, one per line, optionally prefixed with 'list:'")
 }
 
-fn get_string(id: &str, args: &ArgMatches) -> Option<String> {
+/// `--stdin` for commands on existing tasks, which also take positions.
+fn stdin_lookup_arg() -> clap::Arg {
+    arg!(stdin: --stdin "Read task names or 1-based positions in the list from stdin, one per line, optionally prefixed with 'list:'")
+}
+
+/// With `--stdin` the only positional argument is the list name.
+fn get_task_and_list(args: &ArgMatches) -> Result<(Option<String>, Option<String>), String> {
+    let task = get_string("task_name", args)?;
+    let list = get_string("list_name", args)?;
+
+    if !args.get_flag("stdin") {
+        return Ok((task, list));
+    }
+
+    match list {
+        Some(_) => Err("With --stdin only the list name can be given".to_string()),
+        None => Ok((None, task)),
+    }
+}
+
+/// Taken as a plain string, so the format can be left out when the file
+/// extension tells it.
+fn format_arg() -> clap::Arg {
+    let formats = FileFormat::value_variants()
+        .iter()
+        .filter_map(|format| format.to_possible_value())
+        .map(|format| format.get_name().to_string())
+        .collect::<Vec<_>>();
+
+    arg!(file_format: <FORMAT>)
+        .help(format!("File format: {} (default: from the file extension)", formats.join(", ")))
+        .required(false)
+}
+
+fn columns_arg() -> clap::Arg {
+    arg!(columns: --columns <MAPPING> "CSV columns as field=Header pairs, e.g. 'name=Task,checked=Done,list'")
+}
+
+fn parse_file_format(format: &str) -> Result<FileFormat, String> {
+    FileFormat::from_str(format, true).map_err(|_| format!("Unknown file format: {}", format))
+}
+
+fn format_of(file: &str) -> Result<FileFormat, String> {
+    FileFormat::from_extension(file).ok_or_else(|| format!("Unable to tell the format of {}, give it before the file", file))
+}
+
+/// `import FORMAT [FILE]` or `import FILE`.
+fn get_import_format(args: &ArgMatches) -> Result<(FileFormat, Option<String>), String> {
+    match (get_string("file_format", args)?, get_string("file", args)?) {
+        (Some(format), Some(file)) => Ok((parse_file_format(&format)?, Some(file))),
+        (Some(format), None) => match parse_file_format(&format) {
+            Ok(format) => Ok((format, None)),
+            Err(_) => Ok((format_of(&format)?, Some(format))),
+        },
+        _ => Err("Give the file format or a file to import".to_string()),
+    }
+}
+
+/// `export FORMAT [LIST]`, `export --format csv [LIST]` or
+/// `export [LIST] -o FILE`.
+fn get_export_format(args: &ArgMatches) -> Result<(FileFormat, Option<String>), String> {
+    let first = get_string("file_format", args)?;
+    let list = get_string("list_name", args)?;
+
+    if let Some(format) = args.get_one::<OutputFormat>("format") {
+        return match (format, list) {
+            (OutputFormat::Csv, None) => Ok((FileFormat::Csv, first)),
+            (OutputFormat::Csv, Some(_)) => Err("With --format only the list name can be given".to_string()),
+            _ => Err("Only csv can be exported with --format".to_string()),
+        };
+    }
+
+    match first {
+        Some(first) => match parse_file_format(&first) {
+            Ok(format) => Ok((format, list)),
+            Err(e) => match (get_string("output", args)?, list) {
+                (Some(output), None) => Ok((format_of(&output)?, Some(first))),
+                _ => Err(e),
+            },
+        },
+        None => match get_string("output", args)? {
+            Some(output) => Ok((format_of(&output)?, None)),
+            None => Err("Give the file format or an output file".to_string()),
+        },
+    }
+}
+
+fn get_format_options(args: &ArgMatches) -> Result<FormatOptions, String> {
+    Ok(FormatOptions { columns: get_string("columns", args)? })
+}
+
+fn count_arg() -> clap::Arg {
+    arg!(count: <N> "Number of operations")
+        .required(false)
+        .default_value("1")
+        .value_parser(clap::value_parser!(u64).range(1..))
+}
+
+fn where_arg() -> clap::Arg {
+    arg!(filter: -w --where <FILTER> "Apply to every task matching the filter query")
+        .conflicts_with_all(["task_name", "stdin", "exact"])
+}
+
+fn get_select_options(args: &ArgMatches) -> Result<SelectOptions, String> {
+    Ok(SelectOptions {
+        exact: args.get_flag("exact"),
+        stdin: args.get_flag("stdin"),
+        filter: get_string("filter", args)?,
+    })
+}
+
+/// `--where`, and `--view` as `view:NAME`.
+fn get_tasks_filter(args: &ArgMatches) -> Result<Option<String>, String> {
+    let view = get_string("view", args)?.map(|view| format!("view:\"{}\"", view));
+    Ok(match (view, get_string("filter", args)?) {
+        (Some(view), Some(filter)) => Some(format!("{} and ({})", view, filter)),
+        (view, filter) => view.or(filter),
+    })
+}
+
+fn get_string(id: &str, args: &ArgMatches) -> Result<Option<String>, String> {
     match args.get_one::<String>(id) {
         Some(list) => match list.as_str() {
-            "" => {
-                eprintln!("The {} can't be an empty string", id);
-                exit(1)
-            }
-            _ => Some(list.into()),
+            "" => Err(format!("The {} can't be an empty string", id)),
+            _ => Ok(Some(list.into())),
         },
-        None => None,
+        None => Ok(None),
+    }
+}
+
+fn get_strings(id: &str, args: &ArgMatches) -> Vec<String> {
+    match args.get_many::<String>(id) {
+        Some(values) => values.cloned().collect(),
+        None => Vec::new(),
     }
 }
@@ -0,0 +1,89 @@
+use std::{io::{ErrorKind, Read, Write}, process::Stdio};
+
+use crate::config::PickerCommand;
+
+pub fn external_select(
+    command: &PickerCommand,
+    options: Vec<String>,
+    multi: bool,
+) -> Result<Vec<String>, String> {
+    let options = options.join("\n");
+
+    let mut args = command.args.clone();
+    if multi {
+        args.extend(command.multi_args.iter().cloned());
+    }
+    args.extend(command.preview_args.iter().map(|arg| arg.replace("{preview}", &preview_command())));
+
+    let picker = std::process::Command::new(&command.program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let mut picker = match picker {
+        Ok(picker) => picker,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(format!(
+                "Picker '{}' not found. Install it or change `picker` in ~/.todo-app/config.toml",
+                command.program
+            ))
+        }
+        Err(e) => {
+            return Err(format!("Failed to run picker '{}': {}", command.program, e))
+        }
+    };
+
+    // Some pickers draw on stderr, so it is passed on as it comes and only
+    // kept for the error message.
+    let mut picker_stderr = picker.stderr.take().ok_or("Failed to open picker stderr")?;
+    let stderr = std::thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut buffer = [0; 4096];
+        while let Ok(n @ 1..) = picker_stderr.read(&mut buffer) {
+            let _ = std::io::stderr().write_all(&buffer[..n]);
+            captured.extend_from_slice(&buffer[..n]);
+        }
+        String::from_utf8_lossy(&captured).to_string()
+    });
+
+    picker.stdin.take()
+        .ok_or("Failed to open picker stdin")?
+        .write_all(options.as_bytes())
+        .map_err(|e| format!("Failed to write data to picker stdin: {}", e))?;
+
+    let output = picker.wait_with_output()
+        .map_err(|e| format!("Failed to read picker stdout: {}", e))?;
+
+    let stderr = stderr.join().unwrap_or_default();
+
+    if output.status.code().is_some_and(|code| command.abort_codes.contains(&code)) {
+        eprintln!("Nothing selected, '{}' was aborted", command.program);
+        return Ok(Vec::new());
+    }
+    if !output.status.success() {
+        let mut error = format!("Picker '{}' failed ({})", command.program, output.status);
+        if let Some(line) = stderr.lines().rfind(|line| !line.trim().is_empty()) {
+            error += &format!(": {}", line.trim());
+        }
+        return Err(error);
+    }
+
+    Ok(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect()
+    )
+}
+
+/// Shell command printing the details of a picker item.
+fn preview_command() -> String {
+    let exe = std::env::current_exe()
+        .map(|exe| exe.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "todo-app".to_string());
+
+    format!("'{}' preview", exe.replace('\'', "'\\''"))
+}
@@ -0,0 +1,143 @@
+//! Machine-readable output for `tasks`, `lists`, `search` and `archive list`.
+//!
+//! The schema is stable: fields may be added at the end, but are never
+//! renamed, reordered or removed.
+//!
+//! Task (`tasks`, `search`, `archive list`):
+//!
+//! | field       | type                          |
+//! |-------------|-------------------------------|
+//! | `list`      | string                        |
+//! | `name`      | string                        |
+//! | `checked`   | bool                          |
+//! | `due`       | `YYYY-MM-DD` or null          |
+//! | `tags`      | array of strings              |
+//! | `priority`  | `A`-`Z` or null               |
+//! | `created`   | RFC 3339 timestamp or null    |
+//! | `completed` | RFC 3339 timestamp or null    |
+//!
+//! List (`lists`): `name` (string), `tasks` (number), `checked` (number).
+//!
+//! `json` prints one array, `ndjson` one object per line. `csv` and `tsv`
+//! start with a header row; tags are joined with `;` and nulls are empty.
+//! `plain` prints `list: name` for tasks and the name for lists, unstyled.
+
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::files::Task;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Ndjson,
+    Csv,
+    Tsv,
+    Plain,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskRecord<'a> {
+    pub list: &'a str,
+    pub name: &'a str,
+    pub checked: bool,
+    pub due: Option<NaiveDate>,
+    pub tags: &'a [String],
+    pub priority: Option<char>,
+    pub created: Option<DateTime<Local>>,
+    pub completed: Option<DateTime<Local>>,
+}
+
+impl<'a> TaskRecord<'a> {
+    pub fn new(list: &'a str, task: &'a Task) -> Self {
+        Self {
+            list,
+            name: &task.name,
+            checked: task.checked,
+            due: task.due,
+            tags: &task.tags,
+            priority: task.priority,
+            created: task.created,
+            completed: task.completed,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListRecord {
+    pub name: String,
+    pub tasks: usize,
+    pub checked: usize,
+}
+
+pub fn print_tasks(tasks: &[(String, Task)], format: OutputFormat) -> Result<(), String> {
+    let records = tasks
+        .iter()
+        .map(|(list, task)| TaskRecord::new(list, task))
+        .collect::<Vec<_>>();
+
+    print_records(
+        &records,
+        format,
+        &["list", "name", "checked", "due", "tags", "priority", "created", "completed"],
+        |r| vec![
+            r.list.to_string(),
+            r.name.to_string(),
+            r.checked.to_string(),
+            r.due.map(|d| d.to_string()).unwrap_or_default(),
+            r.tags.join(";"),
+            r.priority.map(|p| p.to_string()).unwrap_or_default(),
+            r.created.map(|c| c.to_rfc3339()).unwrap_or_default(),
+            r.completed.map(|c| c.to_rfc3339()).unwrap_or_default(),
+        ],
+        |r| format!("{}: {}", r.list, r.name),
+    )
+}
+
+pub fn print_lists(lists: &[ListRecord], format: OutputFormat) -> Result<(), String> {
+    print_records(
+        lists,
+        format,
+        &["name", "tasks", "checked"],
+        |r| vec![r.name.clone(), r.tasks.to_string(), r.checked.to_string()],
+        |r| r.name.clone(),
+    )
+}
+
+fn print_records<T: Serialize>(
+    records: &[T],
+    format: OutputFormat,
+    header: &[&str],
+    row: impl Fn(&T) -> Vec<String>,
+    plain: impl Fn(&T) -> String,
+) -> Result<(), String> {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string(records).map_err(|e| e.to_string())?);
+        }
+        OutputFormat::Ndjson => {
+            for record in records {
+                println!("{}", serde_json::to_string(record).map_err(|e| e.to_string())?);
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(if format == OutputFormat::Csv { b',' } else { b'\t' })
+                .from_writer(std::io::stdout());
+
+            writer.write_record(header).map_err(|e| e.to_string())?;
+            for record in records {
+                writer.write_record(row(record)).map_err(|e| e.to_string())?;
+            }
+            writer.flush().map_err(|e| e.to_string())?;
+        }
+        OutputFormat::Plain => {
+            for record in records {
+                println!("{}", plain(record));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,11 +1,17 @@
 mod app;
 mod parsing;
 mod styles;
-mod fzf;
+mod external;
+mod picker;
 mod utils;
+mod format;
+mod table;
 
 pub use app::*;
 pub use parsing::*;
 pub use styles::*;
-pub use fzf::*;
+pub use external::*;
+pub use picker::*;
 pub use utils::*;
+pub use format::*;
+pub use table::*;
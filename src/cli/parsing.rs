@@ -1,141 +1,585 @@
-use crate::cli::Subcommands;
+use crate::cli::{GlobalOptions, Interaction, LogOptions, SelectOptions, Subcommands, TaskOptions, TasksOptions};
+use crate::backup;
 use crate::config::Settings;
-use crate::files::{self, create_list, delete_list, get_lists, get_tasks};
+use crate::files::{self, create_list, delete_list, get_lists, parse_date, parse_priority, Task, Trashed};
+use crate::filter::Filter;
+use crate::formats::{self, FileFormat, FormatOptions};
+use crate::journal::{Change, Operation};
+use crate::scan;
+use crate::search::{Matcher, SearchMode};
+use crate::sort::{group_tasks, sort_tasks, GroupBy};
 
-use super::{parse_with_fzf, use_style};
+use super::{
+    confirm, confirm_bulk, highlight, parse_with_picker, print_batch, print_check_batch, print_lists, print_table, print_tasks,
+    read_lines, resolve_task, use_style, ListRecord, OutputFormat,
+};
 
-pub fn parse_args(subcmd: Subcommands, config: Settings) {
+pub fn parse_args(subcmd: Subcommands, options: GlobalOptions, config: Settings) {
     match subcmd {
-        Subcommands::Add(task, list) => add(task, list, config),
-        Subcommands::Remove(task, list) => remove(task, list, config),
-        Subcommands::Check(task, list) => check(task, list, config),
-        Subcommands::Tasks(list) => tasks(list, config),
+        Subcommands::Add(task, list, options) => add(task, list, options, config),
+        Subcommands::Remove(task, list, select) => remove(task, list, select, options.interaction, config),
+        Subcommands::Check(task, list, select) => check(task, list, select, options.interaction, config),
+        Subcommands::Tasks(list, tasks_options) => tasks(list, tasks_options, options.format, config),
         Subcommands::AddList(list) => add_list(list, config),
-        Subcommands::RemoveList(list) => remove_list(list, config),
-        Subcommands::Lists => lists(config),
+        Subcommands::RemoveList(list, force) => remove_list(list, force, options.interaction, config),
+        Subcommands::Lists => lists(options.format, config),
+        Subcommands::Search(pattern, mode, ignore_case) => search(pattern, mode, ignore_case, options.format, config),
+        Subcommands::Preview(item) => preview(item, config),
+        Subcommands::Move(list, filter) => move_tasks(list, filter, options.interaction, config),
+        Subcommands::Clean(list) => clean(list, config),
+        Subcommands::ArchiveList(list) => archive_list(list, options.format, config),
+        Subcommands::ArchiveRestore(task, list) => archive_restore(task, list, config),
+        Subcommands::Undo(count) => undo(count, config),
+        Subcommands::Redo(count) => redo(count, config),
+        Subcommands::Log(log_options) => log(log_options, config),
+        Subcommands::Trash => trash(config),
+        Subcommands::TrashRestore(item) => trash_restore(item, config),
+        Subcommands::TrashEmpty => trash_empty(options.interaction, config),
+        Subcommands::BackupNow => backup_now(config),
+        Subcommands::BackupList => backup_list(config),
+        Subcommands::BackupRestore(id) => backup_restore(id, config),
+        Subcommands::Import(format, file, list, format_options) => import(format, file, list, format_options, config),
+        Subcommands::Export(format, list, output, format_options) => export(format, list, output, format_options, config),
+        Subcommands::Scan(path, list) => scan(path, list, config),
     }
 }
 
-pub fn add(task: Option<String>, list: Option<String>, config: Settings) {
-    match (task, list) {
-        (Some(task), None) => {
-            match files::add_task(&task, &config.default_list) {
-                Ok(_) => {
-                    println!("{}", use_style("Task added".to_string(), &config.output.text));
-                }
-                Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
-            };
+pub fn add(task: Option<String>, list: Option<String>, options: TaskOptions, config: Settings) {
+    let list = list.unwrap_or(config.default_list.clone());
+
+    let mut task = Task::new(&task.unwrap_or_default());
+    task.tags = options.tags;
+    if let Some(priority) = options.priority {
+        match parse_priority(&priority) {
+            Ok(priority) => task.priority = Some(priority),
+            Err(e) => {
+                eprintln!("{}", use_style(e, &config.output.err));
+                return;
+            }
         }
-        (Some(task), Some(list)) => {
-            match files::add_task(&task, &list) {
-                Ok(_) => {
-                    println!("{}", use_style("Task added".to_string(), &config.output.text));
-                }
-                Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
-            };
+    }
+    if let Some(due) = options.due {
+        match parse_date(&due) {
+            Ok(due) => task.due = Some(due),
+            Err(e) => {
+                eprintln!("{}", use_style(e, &config.output.err));
+                return;
+            }
         }
-        _ => unreachable!()
     }
-}
 
-pub fn remove(task: Option<String>, list: Option<String>, config: Settings) {
-   match (task, list) {
-        (Some(task), None) => {
-            match files::remove_task(&task, &config.default_list) {
-                Ok(_) => println!("{}", use_style("Task deleted".to_string(), &config.output.text)),
-                Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    if options.stdin {
+        let results = match read_lines() {
+            Ok(lines) => files::add_tasks(&lines, &list, &task),
+            Err(e) => {
+                eprintln!("{}", use_style(e, &config.output.err));
+                return;
             }
+        };
+        print_batch(&results, "added", &config);
+        return;
+    }
+
+    match files::add_task(task, &list) {
+        Ok(_) => {
+            println!("{}", use_style("Task added".to_string(), &config.output.text));
         }
-        (Some(task), Some(list)) => {
-            match files::remove_task(&task, &list) {
-                Ok(_) => println!("{}", use_style("Task deleted".to_string(), &config.output.text)),
-                Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
-            }
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    };
+}
+
+pub fn remove(task: Option<String>, list: Option<String>, options: SelectOptions, interaction: Interaction, config: Settings) {
+    if let Some(filter) = options.filter {
+        let filter = parse_filter(&filter, &config).and_then(|f| confirm_filter(f, "deleted", interaction, &config));
+        if let Some(filter) = filter {
+            print_batch(&files::remove_where(&filter), "deleted", &config);
         }
-        _ => {
-            let tasks = parse_with_fzf();
+        return;
+    }
 
-            let len = tasks.len();
-            
-            for (list, task) in tasks {
-                files::remove_task(&task, &list).unwrap();
+    if options.stdin {
+        let results = match read_lines() {
+            Ok(lines) => files::remove_tasks(&lines, list.as_ref().unwrap_or(&config.default_list), options.exact),
+            Err(e) => {
+                eprintln!("{}", use_style(e, &config.output.err));
+                return;
             }
+        };
+        print_batch(&results, "deleted", &config);
+        return;
+    }
 
-            if len == 1 {
-                println!("{}", use_style("Task deleted".to_string(), &config.output.text));
-            } else {
-                println!("{}", use_style("Tasks deleted".to_string(), &config.output.text))
-            }
+    let tasks = match task {
+        Some(task) => resolve_task(&task, list.as_ref().unwrap_or(&config.default_list), options.exact, "remove", interaction, &config.picker),
+        None => parse_with_picker(interaction, &config.picker),
+    };
+
+    let tasks = match tasks {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+    };
+
+    // One change for all of them, so a single undo brings them back.
+    let results = files::remove_selected(&tasks);
+    for (_, result) in &results {
+        if let Err(e) = result {
+            eprintln!("{}", use_style(e.clone(), &config.output.err));
         }
-   }
+    }
+
+    match results.iter().filter(|(_, result)| result.is_ok()).count() {
+        0 => {}
+        1 => println!("{}", use_style("Task deleted".to_string(), &config.output.text)),
+        _ => println!("{}", use_style("Tasks deleted".to_string(), &config.output.text)),
+    }
 }
 
-pub fn check(task: Option<String>, list: Option<String>, config: Settings) {
-    match (task, list) {
-        (Some(task), None) => {
-            match files::check_task(&task, &config.default_list) {
-                Ok(true) => println!("{}", use_style("Task checked".to_string(), &config.output.text)),
-                Ok(false) => println!("{}", use_style("Task unchecked".to_string(), &config.output.text)),
-                Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+pub fn check(task: Option<String>, list: Option<String>, options: SelectOptions, interaction: Interaction, config: Settings) {
+    if let Some(filter) = options.filter {
+        let filter = parse_filter(&filter, &config)
+            .and_then(|f| confirm_filter(f, "checked/unchecked", interaction, &config));
+        if let Some(filter) = filter {
+            print_check_batch(&files::check_where(&filter), &config);
+        }
+        return;
+    }
+
+    if options.stdin {
+        let results = match read_lines() {
+            Ok(lines) => files::check_tasks(&lines, list.as_ref().unwrap_or(&config.default_list), options.exact),
+            Err(e) => {
+                eprintln!("{}", use_style(e, &config.output.err));
+                return;
             }
+        };
+        print_check_batch(&results, &config);
+        return;
+    }
+
+    let tasks = match task {
+        Some(task) => resolve_task(&task, list.as_ref().unwrap_or(&config.default_list), options.exact, "check", interaction, &config.picker),
+        None => parse_with_picker(interaction, &config.picker),
+    };
+
+    let tasks = match tasks {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+    };
+
+    if tasks.len() == 1 {
+        match files::check_task(&tasks[0].1, &tasks[0].0) {
+            Ok(true) => println!("{}", use_style("Task checked".to_string(), &config.output.text)),
+            Ok(false) => println!("{}", use_style("Task unchecked".to_string(), &config.output.text)),
+            Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
         }
-        (Some(task), Some(list)) => {
-            match files::check_task(&task, &list) {
-                Ok(true) => println!("{}", use_style("Task checked".to_string(), &config.output.text)),
-                Ok(false) => println!("{}", use_style("Task unchecked".to_string(), &config.output.text)),
+    } else {
+        for ((_, task), (_, result)) in tasks.iter().zip(files::check_selected(&tasks)) {
+            match result {
+                Ok(true) => println!("{}", use_style(format!("\"{}\" checked", task), &config.output.text)),
+                Ok(false) => println!("{}", use_style(format!("\"{}\" unchecked", task), &config.output.text)),
                 Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
-            }
+            };
         }
-        _ => {
-            let tasks = parse_with_fzf();
-
-            let len = tasks.len();
-            
-            
-            if len == 1 {
-                match files::check_task(&tasks[0].1, &tasks[0].0) {
-                    Ok(true) => println!("{}", use_style("Task checked".to_string(), &config.output.text)),
-                    Ok(false) => println!("{}", use_style("Task unchecked".to_string(), &config.output.text)),
-                    Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
-                }
-            } else {
-                for (list, task) in tasks {
-                    match files::check_task(&task, &list) {
-                        Ok(true) => println!("{}", use_style(format!("\"{}\" checked", task), &config.output.text)),
-                        Ok(false) => println!("{}", use_style(format!("\"{}\" unchecked", task), &config.output.text)),
-                        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
-                    };
-                }
+    }
+}
+
+pub fn tasks(list: Option<String>, options: TasksOptions, format: Option<OutputFormat>, config: Settings) {
+    let mut filter = match options.filter.map(|filter| Filter::parse_with_views(&filter, &config.views)) {
+        Some(Ok(filter)) => Some(filter),
+        Some(Err(e)) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+        None => None,
+    };
+
+    if let Some(list) = &list {
+        if !get_lists().contains(list) {
+            eprintln!("{}", use_style(files::LIST_NOT_FOUND.to_string(), &config.output.err));
+            return;
+        }
+        let by_list = Filter::List(list.to_string());
+        filter = Some(match filter {
+            Some(filter) => Filter::And(Box::new(by_list), Box::new(filter)),
+            None => by_list,
+        });
+    }
+
+    let mut tasks = match &filter {
+        Some(filter) => files::filter_tasks(filter),
+        None => files::get_all_tasks(),
+    };
+
+    if let Some(key) = options.sort {
+        sort_tasks(&mut tasks, key);
+    }
+
+    if let Some(format) = format {
+        if let Err(e) = print_tasks(&tasks, format) {
+            eprintln!("{}", use_style(e, &config.output.err));
+        }
+        return;
+    }
+
+    if options.table {
+        print_table(&tasks, &config.output);
+        return;
+    }
+
+    let group_by = match (options.group_by, &list) {
+        (Some(group_by), _) => Some(group_by),
+        (None, None) => Some(GroupBy::List),
+        (None, Some(_)) => None,
+    };
+
+    match group_by {
+        Some(group_by) => {
+            for (label, tasks) in group_tasks(tasks, group_by) {
+                println!("{}", use_style(format!("[{}]", label), &config.output.list));
+                tasks.iter().for_each(|(_, task)| {
+                    println!("{}", use_style(task.to_string(), &config.output.text));
+                });
             }
         }
-   }
+        None => {
+            tasks.iter().for_each(|(_, task)| {
+                println!("{}", use_style(task.to_string(), &config.output.text));
+            });
+        }
+    }
 }
 
-pub fn tasks(list: Option<String>, config: Settings) {
-    match list {
-        Some(list) => {
-            match files::get_tasks(Some(&list)) {
-                Ok(tasks) => {
-                    tasks.iter().for_each(|task| println!("{}", use_style(task.to_string(), &config.output.text)));
-                }
-                Err(e) => {
-                    eprintln!("{}", use_style(e, &config.output.err))
-                }
+pub fn move_tasks(list: Option<String>, filter: Option<String>, interaction: Interaction, config: Settings) {
+    let list = list.unwrap();
+
+    if !get_lists().contains(&list) {
+        eprintln!("{}", use_style(files::LIST_NOT_FOUND.to_string(), &config.output.err));
+        return;
+    }
+
+    let Some(filter) = parse_filter(&filter.unwrap(), &config) else {
+        return;
+    };
+    let filter = Filter::And(Box::new(filter), Box::new(Filter::Not(Box::new(Filter::List(list.clone())))));
+
+    // Exactly the tasks that were shown are moved, even if the file changes
+    // while the user is asked.
+    let tasks = files::filter_tasks(&filter);
+    match confirm_bulk(&tasks, &format!("moved to {}", list), interaction, &config) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+    }
+
+    let tasks = tasks.into_iter().map(|(list, task)| (list, task.name)).collect::<Vec<_>>();
+    print_batch(&files::move_tasks(&tasks, &list), "moved", &config);
+}
+
+pub fn clean(list: Option<String>, config: Settings) {
+    match files::clean(list.as_deref()) {
+        Ok(tasks) => {
+            let count = if tasks.len() == 1 { "1 task".to_string() } else { format!("{} tasks", tasks.len()) };
+            println!("{}", use_style(format!("{} archived", count), &config.output.text));
+        }
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn archive_list(list: Option<String>, format: Option<OutputFormat>, config: Settings) {
+    let mut tasks = files::get_archive();
+    if let Some(list) = &list {
+        tasks.retain(|(archived_from, _)| archived_from == list);
+    }
+
+    if let Some(format) = format {
+        if let Err(e) = print_tasks(&tasks, format) {
+            eprintln!("{}", use_style(e, &config.output.err));
+        }
+        return;
+    }
+
+    if tasks.is_empty() {
+        eprintln!("{}", use_style("The archive is empty".to_string(), &config.output.err));
+        return;
+    }
+
+    for (label, tasks) in group_tasks(tasks, GroupBy::List) {
+        println!("{}", use_style(format!("[{}]", label), &config.output.list));
+        for (_, task) in tasks {
+            let completed = task.completed.map(|c| format!(" (completed {})", c.format("%Y-%m-%d"))).unwrap_or_default();
+            println!("{}", use_style(format!("{}{}", task, completed), &config.output.text));
+        }
+    }
+}
+
+pub fn archive_restore(task: Option<String>, list: Option<String>, config: Settings) {
+    match files::restore_archived(&task.unwrap(), list.as_deref()) {
+        Ok(list) => println!("{}", use_style(format!("Task restored to {}", list), &config.output.text)),
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn undo(count: usize, config: Settings) {
+    match files::undo(count) {
+        Ok(operations) => print_operations(&operations, "Undone", true, &config),
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn redo(count: usize, config: Settings) {
+    match files::redo(count) {
+        Ok(operations) => print_operations(&operations, "Redone", false, &config),
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn log(options: LogOptions, config: Settings) {
+    let parse = |date: Option<String>| date.map(|date| parse_date(&date)).transpose();
+    let (since, until) = match (parse(options.since), parse(options.until)) {
+        (Ok(since), Ok(until)) => (since, until),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+    };
+
+    let history = files::get_history()
+        .into_iter()
+        .filter(|entry| options.list.is_none() || options.list.as_ref() == Some(&entry.list))
+        .filter(|entry| since.is_none() || since <= Some(entry.time.date_naive()))
+        .filter(|entry| until.is_none() || until >= Some(entry.time.date_naive()))
+        .collect::<Vec<_>>();
+
+    if history.is_empty() {
+        eprintln!("{}", use_style("No changes found".to_string(), &config.output.err));
+        return;
+    }
+
+    for entry in history {
+        let task = entry.task.map(|task| format!(" {}", use_style(task, &config.output.text))).unwrap_or_default();
+        println!(
+            "{} {}{}",
+            use_style(format!("{} {:<11}", entry.time.format("%Y-%m-%d %H:%M"), entry.action), &config.output.text),
+            use_style(format!("[{}]", entry.list), &config.output.list),
+            task,
+        );
+    }
+}
+
+pub fn trash(config: Settings) {
+    let trash = files::get_trash();
+
+    if trash.is_empty() {
+        eprintln!("{}", use_style("The trash is empty".to_string(), &config.output.err));
+        return;
+    }
+
+    for trashed in trash {
+        let (list, item) = match trashed.item {
+            Trashed::Task { list, task } => (list, task.to_string()),
+            Trashed::List { list, tasks } if tasks.len() == 1 => (list, "(list, 1 task)".to_string()),
+            Trashed::List { list, tasks } => (list, format!("(list, {} tasks)", tasks.len())),
+        };
+        println!(
+            "{} {} {}",
+            use_style(trashed.deleted.format("%Y-%m-%d %H:%M").to_string(), &config.output.text),
+            use_style(format!("[{}]", list), &config.output.list),
+            use_style(item, &config.output.text),
+        );
+    }
+}
+
+pub fn trash_restore(item: Option<String>, config: Settings) {
+    match files::restore_trashed(&item.unwrap()) {
+        Ok(trashed) => {
+            let message = match trashed.item {
+                Trashed::Task { list, .. } => format!("Task restored to {}", list),
+                Trashed::List { .. } => "List restored".to_string(),
+            };
+            println!("{}", use_style(message, &config.output.text));
+        }
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn trash_empty(interaction: Interaction, config: Settings) {
+    let count = files::get_trash().len();
+    if count == 0 {
+        eprintln!("{}", use_style("The trash is empty".to_string(), &config.output.err));
+        return;
+    }
+
+    let items = if count == 1 { "1 item".to_string() } else { format!("{} items", count) };
+    match confirm(&format!("Delete {} in the trash for good?", items), interaction) {
+        Ok(true) => {
+            files::empty_trash();
+            println!("{}", use_style("Trash emptied".to_string(), &config.output.text));
+        }
+        Ok(false) => {}
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn backup_now(config: Settings) {
+    match backup::create() {
+        Ok(id) => println!("{}", use_style(format!("Backup {} created", id), &config.output.text)),
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn backup_list(config: Settings) {
+    let backups = backup::list();
+
+    if backups.is_empty() {
+        eprintln!("{}", use_style("No backups yet".to_string(), &config.output.err));
+        return;
+    }
+
+    for backup in backups {
+        let summary = match backup::read(&backup.id) {
+            Ok(data) => {
+                let count = |n: usize, noun: &str| format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" });
+                format!("{}, {}", count(data.len(), "list"), count(data.values().map(Vec::len).sum(), "task"))
             }
+            Err(e) => e,
+        };
+        println!(
+            "{} {}",
+            use_style(backup.id, &config.output.list),
+            use_style(summary, &config.output.text),
+        );
+    }
+}
+
+pub fn backup_restore(id: Option<String>, config: Settings) {
+    match files::restore_backup(&id.unwrap()) {
+        Ok(()) => println!("{}", use_style("Backup restored".to_string(), &config.output.text)),
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn import(format: FileFormat, file: Option<String>, list: Option<String>, options: FormatOptions, config: Settings) {
+    let text = match file {
+        Some(file) => std::fs::read_to_string(&file).map_err(|e| format!("Unable to read {}: {}", file, e)),
+        None => std::io::read_to_string(std::io::stdin()).map_err(|e| format!("Unable to read stdin: {}", e)),
+    };
+
+    let tasks = text.and_then(|text| {
+        formats::import(format, &text, list.as_ref().unwrap_or(&config.default_list), &options)
+    });
+
+    match tasks {
+        Ok(tasks) => print_batch(&files::import_tasks(tasks), "imported", &config),
+        Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
+    }
+}
+
+pub fn export(format: FileFormat, list: Option<String>, output: Option<String>, options: FormatOptions, config: Settings) {
+    let tasks = match &list {
+        Some(list) if !get_lists().contains(list) => {
+            eprintln!("{}", use_style(files::LIST_NOT_FOUND.to_string(), &config.output.err));
+            return;
         }
-        None => {
-            get_lists().iter().for_each(|list| {
-                let tasks = get_tasks(Some(list)).unwrap();
-                if !tasks.is_empty() {
-                    println!("{}", use_style(
-                        format!("[{}]", list).to_string(),
-                        &config.output.list
-                    ));
-                    tasks.iter().for_each(|task| {
-                        println!("{}", use_style(task.to_string(), &config.output.text));
-                    });
-                }
-            })
+        Some(list) => files::filter_tasks(&Filter::List(list.to_string())),
+        None => files::get_all_tasks(),
+    };
+
+    let text = match formats::export(format, &tasks, &options) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+    };
+
+    match output {
+        Some(file) => match std::fs::write(&file, text) {
+            Ok(()) => {
+                let count = if tasks.len() == 1 { "1 task".to_string() } else { format!("{} tasks", tasks.len()) };
+                println!("{}", use_style(format!("{} exported", count), &config.output.text));
+            }
+            Err(e) => eprintln!("{}", use_style(format!("Unable to write {}: {}", file, e), &config.output.err)),
+        },
+        None => print!("{}", text),
+    }
+}
+
+pub fn scan(path: Option<String>, list: Option<String>, config: Settings) {
+    let root = std::path::PathBuf::from(path.unwrap_or(".".to_string()));
+    let comments = match scan::scan(&root) {
+        Ok(comments) => comments,
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+    };
+
+    let list = list.unwrap_or_else(|| scan::project_name(&root));
+    let summary = files::sync_scanned(&list, &comments);
+
+    let found = if comments.len() == 1 { "1 comment".to_string() } else { format!("{} comments", comments.len()) };
+    let changes = [
+        (summary.added, "added"),
+        (summary.moved, "moved"),
+        (summary.checked, "checked"),
+        (summary.reopened, "reopened"),
+    ]
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, action)| format!("{} {}", n, action))
+    .collect::<Vec<_>>();
+
+    let message = if changes.is_empty() {
+        format!("{} found in {}, nothing changed", found, list)
+    } else {
+        format!("{} found in {}: {}", found, list, changes.join(", "))
+    };
+    println!("{}", use_style(message, &config.output.text));
+}
+
+/// Prints the changes of the operations. Moving things to or out of the
+/// trash is left out when the removal or restore itself is printed.
+fn print_operations(operations: &[Operation], action: &str, reverse: bool, config: &Settings) {
+    for operation in operations {
+        let changes: Box<dyn Iterator<Item = _>> = if reverse {
+            Box::new(operation.changes.iter().rev())
+        } else {
+            Box::new(operation.changes.iter())
+        };
+        let trash_only = operation.changes.iter().all(|change| matches!(change, Change::Trash { .. }));
+        for change in changes.filter(|change| trash_only || !matches!(change, Change::Trash { .. })) {
+            println!("{}", use_style(format!("{}: {}", action, change), &config.output.text));
+        }
+    }
+}
+
+fn parse_filter(filter: &str, config: &Settings) -> Option<Filter> {
+    match Filter::parse_with_views(filter, &config.views) {
+        Ok(filter) => Some(filter),
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            None
+        }
+    }
+}
+
+/// Asks to confirm the change of the tasks matching the filter.
+fn confirm_filter(filter: Filter, action: &str, interaction: Interaction, config: &Settings) -> Option<Filter> {
+    match confirm_bulk(&files::filter_tasks(&filter), action, interaction, config) {
+        Ok(true) => Some(filter),
+        Ok(false) => None,
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            None
         }
     }
 }
@@ -147,13 +591,156 @@ pub fn add_list(list: Option<String>, config: Settings) {
     }
 }
 
-pub fn remove_list(list: Option<String>, config: Settings) {
-    match delete_list(&list.unwrap()) {
+/// Asks before removing a list that still has tasks. The default list is
+/// only removed with `--force`.
+pub fn remove_list(list: Option<String>, force: bool, interaction: Interaction, config: Settings) {
+    let list = list.unwrap();
+
+    if !force {
+        if list == config.default_list {
+            eprintln!("{}", use_style("Refusing to remove the default list without --force".to_string(), &config.output.err));
+            return;
+        }
+
+        let tasks = match files::get_tasks(Some(&list)) {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                eprintln!("{}", use_style(e, &config.output.err));
+                return;
+            }
+        };
+
+        if !tasks.is_empty() {
+            let completed = tasks.iter().filter(|task| task.checked).count();
+            println!(
+                "{}",
+                use_style(
+                    format!("{} has {} open and {} completed tasks", list, tasks.len() - completed, completed),
+                    &config.output.text,
+                )
+            );
+            match confirm("Remove it anyway?", interaction) {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(e) => {
+                    eprintln!("{}", use_style(e, &config.output.err));
+                    return;
+                }
+            }
+        }
+    }
+
+    match delete_list(&list) {
         Ok(_) => println!("{}", use_style("List removed".to_string(), &config.output.text)),
         Err(e) => eprintln!("{}", use_style(e, &config.output.err)),
     }
 }
 
-pub fn lists(config: Settings) {
-    get_lists().iter().for_each(|task| println!("{}", use_style(task.to_string(), &config.output.text)));
+pub fn lists(format: Option<OutputFormat>, config: Settings) {
+    let format = match format {
+        Some(format) => format,
+        None => {
+            get_lists().iter().for_each(|task| println!("{}", use_style(task.to_string(), &config.output.text)));
+            return;
+        }
+    };
+
+    let tasks = files::get_all_tasks();
+    let lists = get_lists()
+        .into_iter()
+        .map(|name| {
+            let in_list = tasks.iter().filter(|(list, _)| *list == name);
+            ListRecord {
+                tasks: in_list.clone().count(),
+                checked: in_list.filter(|(_, task)| task.checked).count(),
+                name,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if let Err(e) = print_lists(&lists, format) {
+        eprintln!("{}", use_style(e, &config.output.err));
+    }
+}
+
+pub fn search(pattern: Option<String>, mode: SearchMode, ignore_case: bool, format: Option<OutputFormat>, config: Settings) {
+    let matcher = match Matcher::new(&pattern.unwrap(), mode, ignore_case) {
+        Ok(matcher) => matcher,
+        Err(e) => {
+            eprintln!("{}", use_style(e, &config.output.err));
+            return;
+        }
+    };
+
+    let found = files::search_tasks(&matcher);
+
+    if let Some(format) = format {
+        if let Err(e) = print_tasks(&found, format) {
+            eprintln!("{}", use_style(e, &config.output.err));
+        }
+        return;
+    }
+
+    if found.is_empty() {
+        eprintln!("{}", use_style("Nothing found".to_string(), &config.output.err));
+        return;
+    }
+
+    for (list, task) in found {
+        println!(
+            "{} {}{}",
+            use_style(format!("[{}]", list), &config.output.list),
+            use_style(format!("{} ", if task.checked {"☑"} else {"x"}), &config.output.text),
+            highlight(&task.name, &matcher.find_all(&task.name), &config.output),
+        );
+    }
+}
+
+pub fn preview(item: Option<String>, config: Settings) {
+    let item = item.unwrap();
+    let tasks = files::get_all_tasks();
+
+    if item == "All" || get_lists().contains(&item) {
+        tasks.iter()
+            .filter(|(list, _)| item == "All" || *list == item)
+            .for_each(|(_, task)| println!("{}", use_style(task.to_string(), &config.output.text)));
+        return;
+    }
+
+    let found = tasks.iter()
+        .find(|(list, task)| item.strip_prefix(&format!("{}: ", list)) == Some(task.name.as_str()))
+        .or_else(|| tasks.iter().find(|(_, task)| task.name == item));
+
+    let (list, task) = match found {
+        Some(found) => found,
+        None => {
+            eprintln!("{}", use_style(files::TASK_NOT_FOUND.to_string(), &config.output.err));
+            return;
+        }
+    };
+
+    let mut details = vec![
+        ("Name", task.name.clone()),
+        ("List", list.clone()),
+        ("Status", if task.checked { "done" } else { "open" }.to_string()),
+    ];
+    if let Some(priority) = task.priority {
+        details.push(("Priority", priority.to_string()));
+    }
+    if let Some(due) = task.due {
+        details.push(("Due", due.to_string()));
+    }
+    if !task.tags.is_empty() {
+        details.push(("Tags", task.tags.join(", ")));
+    }
+    if let Some(created) = task.created {
+        details.push(("Created", created.format("%Y-%m-%d %H:%M").to_string()));
+    }
+    if let Some(completed) = task.completed {
+        details.push(("Completed", completed.format("%Y-%m-%d %H:%M").to_string()));
+    }
+
+    for (key, value) in details {
+        println!("{} {}", use_style(format!("{}:", key), &config.output.list), use_style(value, &config.output.text));
+    }
 }
@@ -0,0 +1,292 @@
+use std::collections::BTreeSet;
+use std::io::{stderr, IsTerminal, Stderr};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{List, ListState, Paragraph};
+use ratatui::Terminal;
+
+use crate::config::Picker;
+use crate::search::fuzzy_score;
+
+use super::external_select;
+
+/// Lets the user pick one or (with `multi`) several options with `picker`.
+/// Returns an empty vector if the user aborted.
+pub fn select(options: Vec<String>, multi: bool, picker: &Picker) -> Result<Vec<String>, String> {
+    match picker.command() {
+        Some(command) => external_select(&command, options, multi),
+        None => pick(&options, multi),
+    }
+}
+
+/// Built-in fuzzy picker drawn on stderr, so stdout can still be piped.
+pub fn pick(options: &[String], multi: bool) -> Result<Vec<String>, String> {
+    if options.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if !stderr().is_terminal() || !std::io::stdin().is_terminal() {
+        return Err("The picker needs an interactive terminal".to_string());
+    }
+
+    let _guard = TerminalGuard::new()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stderr())).map_err(|e| e.to_string())?;
+    PickerState::new(options, multi).run(&mut terminal)
+}
+
+/// Raw mode and the alternate screen for as long as it lives. Dropping it
+/// restores the terminal, also when the picker fails or panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> Result<Self, String> {
+        enable_raw_mode().map_err(|e| e.to_string())?;
+        let guard = TerminalGuard;
+        execute!(stderr(), EnterAlternateScreen).map_err(|e| e.to_string())?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(stderr(), LeaveAlternateScreen, crossterm::cursor::Show);
+    }
+}
+
+struct PickerState<'a> {
+    options: &'a [String],
+    multi: bool,
+    query: String,
+    /// Indices into `options` matching the query, best first.
+    matches: Vec<usize>,
+    cursor: usize,
+    selected: BTreeSet<usize>,
+}
+
+impl<'a> PickerState<'a> {
+    fn new(options: &'a [String], multi: bool) -> Self {
+        let mut state = Self {
+            options,
+            multi,
+            query: String::new(),
+            matches: Vec::new(),
+            cursor: 0,
+            selected: BTreeSet::new(),
+        };
+        state.update_matches();
+        state
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored = self.options
+            .iter()
+            .enumerate()
+            .filter_map(|(i, option)| fuzzy_score(&self.query, option).map(|score| (score, i)))
+            .collect::<Vec<_>>();
+
+        if !self.query.is_empty() {
+            scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        }
+
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.cursor = 0;
+    }
+
+    fn run(mut self, terminal: &mut Terminal<CrosstermBackend<Stderr>>) -> Result<Vec<String>, String> {
+        loop {
+            terminal.draw(|frame| self.draw(frame)).map_err(|e| e.to_string())?;
+
+            let key = match event::read().map_err(|e| e.to_string())? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            match self.handle_key(key) {
+                Some(Action::Confirm) => return Ok(self.result()),
+                Some(Action::Abort) => return Ok(Vec::new()),
+                None => {}
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Some(Action::Abort),
+            KeyCode::Char('c') if ctrl => return Some(Action::Abort),
+            KeyCode::Enter => return Some(Action::Confirm),
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Char('p') if ctrl => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('n') if ctrl => self.move_cursor(1),
+            KeyCode::Tab if self.multi => {
+                if let Some(&option) = self.matches.get(self.cursor) {
+                    if !self.selected.remove(&option) {
+                        self.selected.insert(option);
+                    }
+                    self.move_cursor(1);
+                }
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update_matches();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.update_matches();
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if !self.matches.is_empty() {
+            let len = self.matches.len() as isize;
+            self.cursor = (self.cursor as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    fn result(&self) -> Vec<String> {
+        if self.selected.is_empty() {
+            self.matches
+                .get(self.cursor)
+                .map(|&option| vec![self.options[option].clone()])
+                .unwrap_or_default()
+        } else {
+            self.selected.iter().map(|&option| self.options[option].clone()).collect()
+        }
+    }
+
+    fn draw(&self, frame: &mut ratatui::Frame) {
+        let [prompt, list, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.size());
+
+        frame.render_widget(Paragraph::new(format!("> {}", self.query)), prompt);
+
+        let items = self.matches.iter().map(|&option| {
+            let mark = if self.selected.contains(&option) { "● " } else { "  " };
+            Line::from(format!("{}{}", mark, self.options[option]))
+        });
+        let mut state = ListState::default().with_selected(Some(self.cursor));
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(Style::new().reversed()),
+            list,
+            &mut state,
+        );
+
+        let hint = if self.multi {
+            "Tab select · Enter confirm · Esc cancel"
+        } else {
+            "Enter confirm · Esc cancel"
+        };
+        frame.render_widget(
+            Paragraph::new(format!("{}/{}  {}", self.matches.len(), self.options.len(), hint)).dim(),
+            help,
+        );
+
+        frame.set_cursor(prompt.x + 2 + self.query.chars().count() as u16, prompt.y);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Confirm,
+    Abort,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> Vec<String> {
+        ["default: buy milk", "work: fix login", "work: fix layout", "home: call mom"].map(String::from).to_vec()
+    }
+
+    /// Feeds the keys and returns the action of the last one.
+    fn press(state: &mut PickerState, keys: &[KeyCode]) -> Option<Action> {
+        keys.iter().map(|&code| state.handle_key(KeyEvent::new(code, KeyModifiers::NONE))).last().flatten()
+    }
+
+    fn typed(text: &str) -> Vec<KeyCode> {
+        text.chars().map(KeyCode::Char).collect()
+    }
+
+    #[test]
+    fn enter_picks_the_option_under_the_cursor() {
+        let options = options();
+        let mut state = PickerState::new(&options, false);
+
+        assert_eq!(press(&mut state, &[KeyCode::Down, KeyCode::Down, KeyCode::Up, KeyCode::Enter]), Some(Action::Confirm));
+        assert_eq!(state.result(), ["work: fix login"]);
+
+        // The cursor wraps around both ends.
+        press(&mut state, &[KeyCode::Up, KeyCode::Up]);
+        assert_eq!(state.result(), ["home: call mom"]);
+        press(&mut state, &[KeyCode::Down]);
+        assert_eq!(state.result(), ["default: buy milk"]);
+    }
+
+    #[test]
+    fn typing_filters_and_ranks_the_options() {
+        let options = options();
+        let mut state = PickerState::new(&options, false);
+
+        press(&mut state, &typed("fixlay"));
+        assert_eq!(state.result(), ["work: fix layout"]);
+
+        press(&mut state, &[KeyCode::Backspace; 3]);
+        assert_eq!(state.matches.len(), 2);
+
+        press(&mut state, &typed("zzz"));
+        assert!(state.matches.is_empty());
+        assert_eq!(press(&mut state, &[KeyCode::Down, KeyCode::Enter]), Some(Action::Confirm));
+        assert!(state.result().is_empty());
+    }
+
+    #[test]
+    fn tab_selects_several_options_only_in_multi_mode() {
+        let options = options();
+        let mut state = PickerState::new(&options, true);
+
+        // Tab toggles and moves down, the result keeps the option order.
+        press(&mut state, &[KeyCode::Down, KeyCode::Down, KeyCode::Tab, KeyCode::Up, KeyCode::Up, KeyCode::Up, KeyCode::Tab]);
+        assert_eq!(state.result(), ["default: buy milk", "work: fix layout"]);
+        press(&mut state, &[KeyCode::Up, KeyCode::Tab]);
+        assert_eq!(state.result(), ["work: fix layout"]);
+
+        let mut single = PickerState::new(&options, false);
+        press(&mut single, &[KeyCode::Tab]);
+        assert!(single.selected.is_empty());
+        assert_eq!(single.result(), ["default: buy milk"]);
+    }
+
+    #[test]
+    fn escape_and_ctrl_c_abort() {
+        let options = options();
+        let mut state = PickerState::new(&options, false);
+
+        assert_eq!(press(&mut state, &[KeyCode::Esc]), Some(Action::Abort));
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(state.handle_key(ctrl_c), Some(Action::Abort));
+        // Other control keys don't end up in the query.
+        assert_eq!(state.handle_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL)), None);
+        assert_eq!(state.query, "");
+        assert_eq!(state.result(), ["work: fix login"]);
+    }
+}
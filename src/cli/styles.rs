@@ -1,6 +1,8 @@
+use std::ops::Range;
+
 use colored::{ColoredString, Colorize};
 
-use crate::config::TextSettings;
+use crate::config::{OutputSettings, TextSettings};
 
 pub fn use_style(text: String, config: &TextSettings) -> ColoredString {
     let mut text = text.color(config.color);
@@ -11,4 +13,23 @@ pub fn use_style(text: String, config: &TextSettings) -> ColoredString {
         text = text.italic()
     }
     text
-}
\ No newline at end of file
+}
+
+pub fn highlight(text: &str, matches: &[Range<usize>], config: &OutputSettings) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+
+    for range in matches {
+        if range.start > last {
+            result += &use_style(text[last..range.start].to_string(), &config.text).to_string();
+        }
+        result += &use_style(text[range.clone()].to_string(), &config.highlight).to_string();
+        last = range.end;
+    }
+
+    if last < text.len() {
+        result += &use_style(text[last..].to_string(), &config.text).to_string();
+    }
+
+    result
+}
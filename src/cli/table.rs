@@ -0,0 +1,171 @@
+use chrono::Local;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::config::OutputSettings;
+use crate::files::Task;
+
+use super::use_style;
+
+const HEADER: [&str; 6] = ["STATUS", "LIST", "NAME", "DUE", "TAGS", "AGE"];
+const NAME: usize = 2;
+const TAGS: usize = 4;
+const LIST: usize = 1;
+const SEPARATOR: &str = "  ";
+const MIN_WIDTH: usize = 6;
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+pub fn print_table(tasks: &[(String, Task)], config: &OutputSettings) {
+    let rows = tasks
+        .iter()
+        .map(|(list, task)| [
+            if task.checked {"☑"} else {"x"}.to_string(),
+            list.to_string(),
+            task.name.to_string(),
+            task.due.map(|due| due.to_string()).unwrap_or_default(),
+            task.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" "),
+            age(task),
+        ])
+        .collect::<Vec<_>>();
+
+    let widths = column_widths(&rows, terminal_width());
+
+    println!("{}", use_style(format_row(&HEADER.map(String::from), &widths), &config.list));
+    for row in &rows {
+        println!("{}", use_style(format_row(row, &widths), &config.text));
+    }
+}
+
+fn terminal_width() -> usize {
+    if let Some(width) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return width;
+    }
+
+    match crossterm::terminal::size() {
+        Ok((width, _)) if width > 0 => width as usize,
+        _ => DEFAULT_TERMINAL_WIDTH,
+    }
+}
+
+/// Natural column widths, shrunk to fit the terminal. The name column gives
+/// way first, then tags, then the list column.
+fn column_widths(rows: &[[String; 6]], max_width: usize) -> [usize; 6] {
+    let mut widths = HEADER.map(|h| h.width());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let total = |widths: &[usize; 6]| widths.iter().sum::<usize>() + SEPARATOR.len() * (widths.len() - 1);
+
+    for column in [NAME, TAGS, LIST] {
+        let overflow = total(&widths).saturating_sub(max_width);
+        if overflow == 0 {
+            break;
+        }
+        let min = HEADER[column].width().max(MIN_WIDTH).min(widths[column]);
+        widths[column] = widths[column].saturating_sub(overflow).max(min);
+    }
+
+    widths
+}
+
+fn format_row(row: &[String; 6], widths: &[usize; 6]) -> String {
+    row.iter()
+        .zip(widths)
+        .map(|(cell, width)| {
+            let cell = truncate(cell, *width);
+            let padding = width - cell.width();
+            format!("{}{}", cell, " ".repeat(padding))
+        })
+        .collect::<Vec<_>>()
+        .join(SEPARATOR)
+        .trim_end()
+        .to_string()
+}
+
+/// Cuts `text` to `width` columns, ending in `…` if anything was cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    result.push('…');
+    result
+}
+
+fn age(task: &Task) -> String {
+    let created = match task.created {
+        Some(created) => created,
+        None => return String::new(),
+    };
+
+    let age = Local::now() - created;
+    if age.num_days() > 0 {
+        format!("{}d", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{}h", age.num_hours())
+    } else {
+        format!("{}m", age.num_minutes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(list: &str, name: &str, tags: &str) -> [String; 6] {
+        ["x", list, name, "2026-10-20", tags, "3d"].map(String::from)
+    }
+
+    #[test]
+    fn columns_shrink_name_then_tags_then_list() {
+        let rows = [row("work", &"n".repeat(40), &["#t"; 10].join(" ")), row("groceries", "milk", "")];
+        let natural = [6, 9, 40, 10, 29, 3];
+        assert_eq!(column_widths(&rows, 200), natural);
+
+        let total = |widths: [usize; 6]| widths.iter().sum::<usize>() + 10;
+        assert_eq!(total(column_widths(&rows, 100)), 100);
+        assert_eq!(column_widths(&rows, 100)[NAME], 33);
+        assert_eq!(column_widths(&rows, 70), [6, 9, 6, 10, 26, 3]);
+        assert_eq!(column_widths(&rows, 40), [6, 6, 6, 10, 6, 3]);
+        // Too narrow for the minimums, which are kept anyway.
+        assert_eq!(column_widths(&rows, 5), [6, 6, 6, 10, 6, 3]);
+    }
+
+    #[test]
+    fn columns_measure_wide_characters() {
+        let rows = [row("日本", "買い物", "")];
+        assert_eq!(column_widths(&rows, 80)[LIST..=NAME], [4, 6]);
+    }
+
+    #[test]
+    fn truncate_keeps_to_the_width() {
+        assert_eq!(truncate("short", 5), "short");
+        assert_eq!(truncate("longer", 5), "long…");
+        assert_eq!(truncate("買い物リスト", 6), "買い…");
+        assert_eq!(truncate("買い物リスト", 5), "買い…");
+        assert_eq!(truncate("買い物", 2), "…");
+        assert_eq!(truncate("abc", 1), "…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn rows_are_padded_to_the_widths() {
+        let widths = [6, 4, 6, 10, 4, 3];
+        assert_eq!(format_row(&row("日本", "買い物リスト", ""), &widths), "x       日本  買い…   2026-10-20        3d");
+    }
+}
@@ -1,93 +1,193 @@
-use std::{collections::HashMap, process::exit};
+use std::io::BufRead;
 
-use crate::files::{get_lists, get_tasks, Task};
+use crate::config::{Picker, Settings};
+use crate::files::{find_task, get_all_tasks, get_lists, get_tasks, BatchResults, Found, Task};
 
-use super::select;
+use super::{select, use_style, Interaction};
+
+pub fn parse_with_picker(interaction: Interaction, picker: &Picker) -> Result<Vec<(String, String)>, String> {
+    if interaction != Interaction::Ask {
+        return Err("No task given and prompts are disabled".to_string());
+    }
 
-pub fn parse_with_fzf() -> Vec<(String, String)> {
     let mut lists = get_lists();
+    lists.retain(|list| !list.is_empty());
     lists.push("All".to_string());
 
-    lists = lists.into_iter().filter(|list| !list.is_empty()).collect();
-
-    let list = &select(lists.clone(), Vec::new()).unwrap()[0];
+    let list = match select(lists, false, picker)?.pop() {
+        Some(list) => list,
+        None => return Ok(Vec::new()),
+    };
 
     let tasks = if list == "All" {
-        lists.pop();
-        lists.into_iter().map(
-            |list| {
-                get_tasks(Some(&list)).unwrap().into_iter().map(
-                    |mut task| {
-                        task.name = format!("{}: {}", list, task.name);
-                        task
-                    }
-                ).collect::<Vec<Task>>()
-            
-            }
-        ).flatten().collect()
+        get_all_tasks()
     } else {
-        get_tasks(Some(list)).unwrap()
+        get_tasks(Some(&list))?.into_iter().map(|task| (list.clone(), task)).collect()
     };
 
-    let tasks = select (
-        tasks
-        .into_iter()
-        .map(|task| task.name)
-        .collect(),
-        vec!["-m".to_string()]
-    )
-    .unwrap();
-
-    tasks.into_iter().map(
-        |task| {
-            let mut task = task.split(": ");
-            let list = if list == "All" {task.next().unwrap()} else {list};
-            let task = task.next().unwrap();
-            (list.to_string(), task.to_string())
-        }
+    let options = tasks
+        .iter()
+        .map(|(task_list, task)| {
+            if list == "All" {
+                format!("{}: {}", task_list, task.name)
+            } else {
+                task.name.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let selected = select(options.clone(), true, picker)?;
+
+    Ok(
+        selected
+            .iter()
+            .filter_map(|s| options.iter().position(|o| o == s))
+            .map(|i| (tasks[i].0.clone(), tasks[i].1.name.clone()))
+            .collect()
     )
-    .collect()
 }
 
-pub fn get_from_all_tasks(data: &mut HashMap<String, Vec<Task>>, task: &str, list: &str, action: &str) -> Result<Vec<String>, String> {
-    let mut tasks = Vec::new();
-    for (other_list, tasks_) in data.iter() {
-        if other_list != list {
-            if tasks_.iter().any(|t| t == task) {
-                tasks.push(other_list.to_string());
+/// Resolves the task the user meant to `(list, task)` pairs, asking when
+/// it is ambiguous. Returns an empty vector if the user declined, and an
+/// error if the task is only in other lists and prompts are answered no.
+pub fn resolve_task(
+    task: &str,
+    list: &str,
+    exact: bool,
+    action: &str,
+    interaction: Interaction,
+    picker: &Picker,
+) -> Result<Vec<(String, String)>, String> {
+    match find_task(task, list, exact)? {
+        Found::Task(task) => Ok(vec![(list.to_string(), task)]),
+        Found::Candidates(candidates) => {
+            if interaction != Interaction::Ask {
+                return Err(format!("Ambiguous task name, candidates: {}", candidates.join(", ")));
             }
+
+            let selected = select(candidates, true, picker)?;
+
+            Ok(selected.into_iter().map(|task| (list.to_string(), task)).collect())
         }
-    }
-    match tasks.len() {
-        0 => Err(crate::files::TASK_NOT_FOUND.to_string()),
-        1 => {
-            println!("The task was found in another list: {}", &tasks[0]);
-            println!("Do you want to {} it? [y/N]", action);
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).expect("Unable to read line");
-            if input.trim().to_ascii_lowercase() == "y" {
-                Ok(vec![tasks[0].clone()])
-            } else {
-                Err("".to_string())
+        Found::OtherLists(lists) => {
+            if matches!(interaction, Interaction::No | Interaction::NonInteractive) {
+                return Err(format!(
+                    "Task not found, but it is in: {}; rerun with --yes to {} it there",
+                    lists.join(", "),
+                    action
+                ));
             }
-        },
-        _ => {
-            println!("The task was found in multiple lists:");
-            for (i, list) in tasks.iter().enumerate() {
-                println!("{}. {}", i + 1, list);
-            }
-            println!("Do you want to {} it in any of them? [y/N]", action);
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).expect("Unable to read line");
 
-            if input.trim().to_ascii_lowercase() == "y" {
-                
-                let lists = select(tasks, Vec::from(["-m".to_string()])).unwrap();
-    
-                Ok(lists)
+            let question = if lists.len() == 1 {
+                eprintln!("The task was found in another list: {}", &lists[0]);
+                format!("Do you want to {} it?", action)
             } else {
-                exit(0);
+                eprintln!("The task was found in multiple lists:");
+                for (i, list) in lists.iter().enumerate() {
+                    eprintln!("{}. {}", i + 1, list);
+                }
+                format!("Do you want to {} it in any of them?", action)
+            };
+
+            if !confirm(&question, interaction)? {
+                return Ok(Vec::new());
             }
+
+            let lists = if lists.len() > 1 && interaction == Interaction::Ask {
+                select(lists, true, picker)?
+            } else {
+                lists
+            };
+
+            Ok(lists.into_iter().map(|list| (list, task.to_string())).collect())
         }
     }
-}
\ No newline at end of file
+}
+
+/// Asks a yes/no question on stderr, defaulting to no. With `--yes`,
+/// `--no` or `--non-interactive` the answer is given without reading stdin.
+pub fn confirm(question: &str, interaction: Interaction) -> Result<bool, String> {
+    match interaction {
+        Interaction::Yes => Ok(true),
+        Interaction::No | Interaction::NonInteractive => Ok(false),
+        Interaction::Ask => {
+            eprintln!("{} [y/N]", question);
+
+            let mut input = String::new();
+            std::io::stdin()
+                .read_line(&mut input)
+                .map_err(|e| format!("Unable to read line: {}", e))?;
+
+            Ok(input.trim().eq_ignore_ascii_case("y"))
+        }
+    }
+}
+
+/// Non-empty lines from stdin, trimmed.
+pub fn read_lines() -> Result<Vec<String>, String> {
+    std::io::stdin()
+        .lock()
+        .lines()
+        .map(|line| line.map(|line| line.trim().to_string()).map_err(|e| format!("Unable to read line: {}", e)))
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .collect()
+}
+
+/// Prints what failed in a batch and a summary like "3 tasks deleted, 1 failed".
+pub fn print_batch<T>(results: &BatchResults<T>, action: &str, config: &Settings) {
+    let failed = print_failures(results, config);
+    println!("{}", use_style(batch_summary(results.len() - failed, action, failed), &config.output.text));
+}
+
+/// Same as [`print_batch`], but tells checked and unchecked tasks apart.
+pub fn print_check_batch(results: &BatchResults<bool>, config: &Settings) {
+    let failed = print_failures(results, config);
+    let checked = results.iter().filter(|(_, result)| *result == Ok(true)).count();
+
+    let mut summary = batch_summary(checked, "checked", 0);
+    summary += &format!(", {} unchecked", results.len() - failed - checked);
+    if failed > 0 {
+        summary += &format!(", {} failed", failed);
+    }
+    println!("{}", use_style(summary, &config.output.text));
+}
+
+fn print_failures<T>(results: &BatchResults<T>, config: &Settings) -> usize {
+    results
+        .iter()
+        .filter_map(|(line, result)| result.as_ref().err().map(|e| (line, e)))
+        .inspect(|(line, e)| eprintln!("{}", use_style(format!("{}: {}", line, e), &config.output.err)))
+        .count()
+}
+
+fn batch_summary(done: usize, action: &str, failed: usize) -> String {
+    let mut summary = format!("{} {} {}", done, if done == 1 { "task" } else { "tasks" }, action);
+    if failed > 0 {
+        summary += &format!(", {} failed", failed);
+    }
+    summary
+}
+
+/// Shows the tasks a bulk operation is about to change and asks to go on.
+pub fn confirm_bulk(
+    tasks: &[(String, Task)],
+    action: &str,
+    interaction: Interaction,
+    config: &Settings,
+) -> Result<bool, String> {
+    if tasks.is_empty() {
+        return Err("No tasks match the filter".to_string());
+    }
+
+    let count = if tasks.len() == 1 { "1 task".to_string() } else { format!("{} tasks", tasks.len()) };
+    println!("{}", use_style(format!("{} will be {}:", count, action), &config.output.text));
+    for (list, task) in tasks {
+        println!(
+            "{} {}",
+            use_style(format!("[{}]", list), &config.output.list),
+            use_style(task.to_string(), &config.output.text)
+        );
+    }
+
+    confirm("Continue?", interaction)
+}
@@ -1,16 +1,126 @@
 use colored::Color;
 use serde::{Deserialize, Serialize};
 
+use crate::filter::Views;
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Settings {
     pub default_list: String,
+    /// Trashed tasks and lists older than this many days are purged,
+    /// `0` keeps them until `trash empty`.
+    #[serde(default = "default_trash_days")]
+    pub trash_days: u32,
+    #[serde(default)]
+    pub picker: Picker,
     pub output: OutputSettings,
+    #[serde(default)]
+    pub backup: BackupSettings,
+    /// Saved filter queries, used as `view:NAME` in a query or with
+    /// `tasks --view NAME`:
+    ///
+    /// ```toml
+    /// [views]
+    /// today = "status:open and due<=today"
+    /// ```
+    #[serde(default)]
+    pub views: Views,
+}
+
+/// How many backups of the tasks to keep:
+///
+/// ```toml
+/// [backup]
+/// last = 10   # the latest ones
+/// daily = 7   # the latest of each day, for today and the 6 days before
+/// weekly = 4  # the latest of each week (Monday to Sunday), for this week and the 3 before
+/// ```
+///
+/// Days and weeks without a backup still count.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BackupSettings {
+    pub last: usize,
+    pub daily: usize,
+    pub weekly: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self { last: 10, daily: 7, weekly: 4 }
+    }
+}
+
+/// Picker used to select lists and tasks interactively. Either the built-in
+/// one, a preset for a known program, or a custom command:
+///
+/// ```toml
+/// [picker.command]
+/// program = "rofi"
+/// args = ["-dmenu", "-i"]
+/// multi_args = ["-multi-select"]
+/// ```
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Picker {
+    #[default]
+    Builtin,
+    Fzf,
+    Sk,
+    Rofi,
+    Dmenu,
+    Gum,
+    Command(PickerCommand),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PickerCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Added to `args` when several items can be selected.
+    #[serde(default)]
+    pub multi_args: Vec<String>,
+    /// Added to `args` to show item details. `{preview}` is replaced with
+    /// the command printing them, e.g. `["--preview", "{preview} {}"]`.
+    #[serde(default)]
+    pub preview_args: Vec<String>,
+    /// Exit codes meaning the user aborted, any other failure is an error.
+    #[serde(default = "default_abort_codes")]
+    pub abort_codes: Vec<i32>,
+}
+
+impl Picker {
+    /// External command to run, `None` for the built-in picker.
+    pub fn command(&self) -> Option<PickerCommand> {
+        let preset = |program: &str, args: &[&str], multi_args: &[&str], preview_args: &[&str]| {
+            PickerCommand {
+                program: program.to_string(),
+                args: args.iter().map(|a| a.to_string()).collect(),
+                multi_args: multi_args.iter().map(|a| a.to_string()).collect(),
+                preview_args: preview_args.iter().map(|a| a.to_string()).collect(),
+                abort_codes: default_abort_codes(),
+            }
+        };
+        let preview = ["--preview", "{preview} {}", "--preview-window", "right:50%:wrap"];
+
+        match self {
+            Picker::Builtin => None,
+            Picker::Fzf => Some(preset("fzf", &[], &["--multi"], &preview)),
+            Picker::Sk => Some(preset("sk", &[], &["--multi"], &preview)),
+            Picker::Rofi => Some(preset("rofi", &["-dmenu", "-i"], &["-multi-select"], &[])),
+            Picker::Dmenu => Some(preset("dmenu", &["-i"], &[], &[])),
+            Picker::Gum => Some(preset("gum", &["choose"], &["--no-limit"], &[])),
+            Picker::Command(command) => Some(command.clone()),
+        }
+    }
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self{
             default_list: "default".to_string(),
+            trash_days: default_trash_days(),
+            picker: Picker::default(),
             output: OutputSettings{
                 text: TextSettings{
                     color: Color::BrightYellow,
@@ -27,16 +137,38 @@ impl Default for Settings {
                     bold: true,
                     italic: false,
                 },
-            }
+                highlight: default_highlight(),
+            },
+            backup: BackupSettings::default(),
+            views: Views::new(),
         }
     }
 }
 
+/// What fzf, sk, rofi, dmenu and gum exit with on Escape or Ctrl-C.
+fn default_abort_codes() -> Vec<i32> {
+    vec![1, 130]
+}
+
+fn default_trash_days() -> u32 {
+    30
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OutputSettings {
     pub text: TextSettings,
     pub err: TextSettings,
     pub list: TextSettings,
+    #[serde(default = "default_highlight")]
+    pub highlight: TextSettings,
+}
+
+fn default_highlight() -> TextSettings {
+    TextSettings{
+        color: Color::BrightRed,
+        bold: true,
+        italic: false,
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
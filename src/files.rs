@@ -1,55 +1,111 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 use std::{collections::HashMap, fs::File, path::PathBuf};
+use uuid::Uuid;
 
-use crate::cli::get_from_all_tasks;
+use crate::backup;
+use crate::filter::Filter;
+use crate::journal::{diff, diff_archive, diff_trash, Change, Files, HistoryEntry, Journal, Operation};
+use crate::scan::{name_key, task_key, Comment, SyncSummary};
+use crate::search::{fuzzy_rank, Matcher};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Task {
     pub name: String,
     pub checked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<char>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<DateTime<Local>>,
+    /// Name of the task in the same list this one is a subtask of, kept
+    /// for formats that nest tasks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+impl Task {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            checked: false,
+            due: None,
+            tags: Vec::new(),
+            priority: None,
+            created: Some(Local::now()),
+            completed: None,
+            parent: None,
+        }
+    }
 }
 
 impl PartialEq for Task {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
     }
-    fn ne(&self, other: &Self) -> bool {
-        self.name != other.name
-    }
 }
 
 impl PartialEq<str> for Task {
     fn eq(&self, other: &str) -> bool {
-        self.name == other.to_string()
-    }
-    fn ne(&self, other: &str) -> bool {
-        self.name != other.to_string()
+        self.name == other
     }
 }
 
-impl Into<clap::builder::Str> for Task {
-    fn into(self) -> clap::builder::Str {
-        clap::builder::Str::from(self.name)
+impl From<Task> for clap::builder::Str {
+    fn from(task: Task) -> Self {
+        clap::builder::Str::from(task.name)
     }
 }
 
 impl std::fmt::Display for Task {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {}",
-            if self.checked {"☑"} else {"x"},
-            self.name
-        )
+        write!(f, "{} ", if self.checked {"☑"} else {"x"})?;
+        if let Some(priority) = self.priority {
+            write!(f, "({}) ", priority)?;
+        }
+        write!(f, "{}", self.name)?;
+        for tag in &self.tags {
+            write!(f, " #{}", tag)?;
+        }
+        if let Some(due) = self.due {
+            write!(f, " (due {})", due)?;
+        }
+        Ok(())
     }
 }
 
-const LIST_NOT_FOUND: &str = "List not found";
+/// Parses a priority letter from `A` (highest) to `Z`.
+pub fn parse_priority(priority: &str) -> Result<char, String> {
+    let mut chars = priority.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(c.to_ascii_uppercase()),
+        _ => Err(format!("Invalid priority: {} (expected A-Z)", priority)),
+    }
+}
+
+/// Parses `YYYY-MM-DD`, `today`, `tomorrow` or `yesterday`.
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    let today = Local::now().date_naive();
+    match date {
+        "today" => Ok(today),
+        "tomorrow" => Ok(today + Duration::days(1)),
+        "yesterday" => Ok(today - Duration::days(1)),
+        _ => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date: {}", date)),
+    }
+}
+
+pub const LIST_NOT_FOUND: &str = "List not found";
 pub const TASK_NOT_FOUND: &str = "Task not found";
 
 fn get_file_data() -> HashMap<String, Vec<Task>> {
-    let mut file_dir = get_dir();
-    file_dir.push("tasks.json");
+    let file_dir = tasks_file();
     if !&file_dir.exists() {
         let temp: HashMap<String, Vec<Task>> = HashMap::new();
         
@@ -65,13 +121,55 @@ fn get_file_data() -> HashMap<String, Vec<Task>> {
     .expect("Unable to deserialize json")
 }
 
+/// Saves the tasks and records the change in the journal.
 fn save_file_data(data: &HashMap<String, Vec<Task>>) {
-    let mut file_dir = get_dir();
-    file_dir.push("tasks.json");
+    save_files(data, None, None);
+}
+
+/// Saves the tasks, and the archive and trash if given, as one journal
+/// operation.
+fn save_files(data: &HashMap<String, Vec<Task>>, archive: Option<&[ArchivedTask]>, trash: Option<&[TrashedItem]>) {
+    let mut changes = diff(&get_file_data(), data);
+    if let Some(archive) = archive {
+        changes.extend(diff_archive(&get_archive_data(), archive));
+    }
+    // Expired entries are only dropped here, so the purge is part of a
+    // journaled operation and the trash on disk always matches the journal.
+    let days = trash_days();
+    let trash = trash.map(|trash| trash.iter().filter(|trashed| !trashed.expired(days)).cloned().collect::<Vec<_>>());
+    if let Some(trash) = &trash {
+        changes.extend(diff_trash(&get_trash_data(), trash));
+    }
+    record(changes);
+
+    write_file_data(data);
+    if let Some(archive) = archive {
+        write_archive_data(archive);
+    }
+    if let Some(trash) = &trash {
+        write_trash_data(trash);
+    }
+
+    // A failed backup must not fail the change itself.
+    if let Err(e) = backup::create() {
+        eprintln!("Unable to back up the tasks: {}", e);
+    }
+}
+
+fn write_file_data(data: &HashMap<String, Vec<Task>>) {
+    let file_dir = tasks_file();
     let file = File::create(&file_dir).expect("Unable to create file");
     serde_json::to_writer(file, data).expect("Unable to write to file");
 }
 
+/// Replaces all lists with the ones in the backup. Like any other change,
+/// this can be undone.
+pub fn restore_backup(id: &str) -> Result<(), String> {
+    let data = backup::read(id)?;
+    save_file_data(&data);
+    Ok(())
+}
+
 pub fn create_list(list: &str) -> Result<(), String> {
     let mut data = get_file_data();
 
@@ -86,113 +184,743 @@ pub fn create_list(list: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Deletes the list, moving it with its tasks to the trash.
 pub fn delete_list(list: &str) -> Result<(), String> {
     let mut data = get_file_data();
 
-    if data.contains_key(list) {
-        data.remove(list);
-        save_file_data(&data);
-        Ok(())
-    } else {
-        Err(LIST_NOT_FOUND.to_string())
+    match data.remove(list) {
+        Some(tasks) => {
+            let mut trash = get_trash_data();
+            trash.push(TrashedItem::new(Trashed::List { list: list.to_string(), tasks }));
+            save_files(&data, None, Some(&trash));
+            Ok(())
+        }
+        None => Err(LIST_NOT_FOUND.to_string()),
     }
 }
 
 pub fn get_lists() -> Vec<String> {
-    get_file_data().keys().map(|x| x.to_string()).collect()
+    let mut lists = get_file_data().keys().map(|x| x.to_string()).collect::<Vec<_>>();
+    lists.sort();
+    lists
 }
 
-pub fn add_task(task: &str, list: &str) -> Result<(), String> {
+pub fn add_task(task: Task, list: &str) -> Result<(), String> {
     let mut data = get_file_data();
 
-    if let Some(tasks) = data.get_mut(list) {
-        let task = Task {
-            name: task.to_string(),
-            checked: false,
-        };
-        if tasks.contains(&task) {
-            return Err("Task already exists".to_string());
+    insert_task(&mut data, task, list)?;
+    save_file_data(&data);
+
+    Ok(())
+}
+
+/// Removes the task, moving it to the trash.
+pub fn remove_task(task: &str, list: &str) -> Result<(), String> {
+    let mut data = get_file_data();
+
+    let task = take_task(&mut data, task, list)?;
+    let mut trash = get_trash_data();
+    trash.push(TrashedItem::new(Trashed::Task { list: list.to_string(), task }));
+    save_files(&data, None, Some(&trash));
+
+    Ok(())
+}
+
+pub fn check_task(task: &str, list: &str) -> Result<bool, String> {
+    let mut data = get_file_data();
+
+    let status = toggle_task(&mut data, task, list)?;
+    save_file_data(&data);
+
+    Ok(status)
+}
+
+/// Outcome of a batch for every line or task it was applied to.
+pub type BatchResults<T> = Vec<(String, Result<T, String>)>;
+
+/// Adds a task for every line, loading and saving the file once.
+/// Each line may start with `list:` to pick another list than `list`.
+pub fn add_tasks(lines: &[String], list: &str, template: &Task) -> BatchResults<()> {
+    batch(line_targets(lines, list), |data, task, list| {
+        let mut new_task = template.clone();
+        new_task.name = task.to_string();
+        insert_task(data, new_task, list)
+    })
+}
+
+/// Removes the task given on every line, see [`lookup_targets`].
+pub fn remove_tasks(lines: &[String], list: &str, exact: bool) -> BatchResults<()> {
+    remove_batch(lookup_targets(lines, list, exact))
+}
+
+/// Checks/unchecks the task given on every line, see [`lookup_targets`].
+pub fn check_tasks(lines: &[String], list: &str, exact: bool) -> BatchResults<bool> {
+    batch(lookup_targets(lines, list, exact), toggle_task)
+}
+
+/// Removes every task matching the filter.
+pub fn remove_where(filter: &Filter) -> BatchResults<()> {
+    remove_batch(filter_targets(filter))
+}
+
+/// Checks/unchecks every task matching the filter.
+pub fn check_where(filter: &Filter) -> BatchResults<bool> {
+    batch(filter_targets(filter), toggle_task)
+}
+
+/// Removes the `(list, task)` pairs, such as the tasks picked in the
+/// picker, as one change.
+pub fn remove_selected(tasks: &[(String, String)]) -> BatchResults<()> {
+    remove_batch(selected_targets(tasks))
+}
+
+/// Checks/unchecks the `(list, task)` pairs as one change.
+pub fn check_selected(tasks: &[(String, String)]) -> BatchResults<bool> {
+    batch(selected_targets(tasks), toggle_task)
+}
+
+/// Moves the `(list, task)` pairs to `to`, such as the tasks a filter
+/// matched when they were shown for confirmation. Tasks whose name is
+/// already taken in `to` stay where they are.
+pub fn move_tasks(tasks: &[(String, String)], to: &str) -> BatchResults<()> {
+    batch(selected_targets(tasks), |data, task, list| {
+        let tasks = data.get(to).ok_or_else(|| LIST_NOT_FOUND.to_string())?;
+        if tasks.iter().any(|t| t == task) {
+            return Err(format!("Task already exists in {}", to));
         }
-        tasks.push(task);
+        let task = take_task(data, task, list)?;
+        insert_task(data, task, to)
+    })
+}
+
+/// Adds imported tasks, creating lists that don't exist yet. Tasks whose
+/// name is already taken in their list are left out.
+pub fn import_tasks(tasks: Vec<(String, Task)>) -> BatchResults<()> {
+    let mut data = get_file_data();
+
+    let results = tasks
+        .into_iter()
+        .map(|(list, task)| {
+            let label = format!("{}: {}", list, task.name);
+            data.entry(list.clone()).or_default();
+            (label, insert_task(&mut data, task, &list))
+        })
+        .collect::<Vec<_>>();
+
+    if results.iter().any(|(_, result)| result.is_ok()) {
         save_file_data(&data);
-        Ok(())
-    } else {
-        Err(LIST_NOT_FOUND.to_string())
     }
+
+    results
 }
 
-pub fn remove_task(task: &str, list: &str) -> Result<(), String> {
+/// Makes the scanned tasks in `list` match `comments`: adds tasks for new
+/// comments, renames the ones whose comment moved, checks the ones whose
+/// comment is gone and unchecks the ones whose comment is back. Tasks that
+/// didn't come from a scan are left alone. Creates the list if needed.
+pub fn sync_scanned(list: &str, comments: &[Comment]) -> SyncSummary {
     let mut data = get_file_data();
+    let created = !data.contains_key(list);
+    let tasks = data.entry(list.to_string()).or_default();
 
-    if let Some(tasks) = data.get_mut(list) {
-        if let Some(idx) = tasks.iter().position(|t| t == task) {
-            let _ = tasks.remove(idx);
-            save_file_data(&data);
-            Ok(())
-        } else {
-            let lists = match get_from_all_tasks(&mut data, task, list, "check") {
-                Ok(lists) => lists,
-                Err(e) => return Err(e),
-            };
-
-            for list in lists {
-                data.entry(list).and_modify(|u| {
-                    u.remove(
-                        u.iter().position(|t| t == task).unwrap()
-                    );
-                });
-            }
+    let names = comments.iter().map(|comment| comment.task_name()).collect::<Vec<_>>();
+    let mut matched = vec![false; tasks.len()];
+    let mut found: Vec<Option<usize>> = vec![None; names.len()];
 
-            save_file_data(&data);
+    // Same line first, so a comment only counts as moved if it did.
+    for (name, found) in names.iter().zip(found.iter_mut()) {
+        *found = tasks.iter().enumerate().position(|(i, task)| !matched[i] && task.name == *name);
+        if let Some(i) = *found {
+            matched[i] = true;
+        }
+    }
+    for (name, found) in names.iter().zip(found.iter_mut()).filter(|(_, found)| found.is_none()) {
+        *found = tasks
+            .iter()
+            .enumerate()
+            .position(|(i, task)| !matched[i] && task_key(task).is_some() && task_key(task) == name_key(name));
+        if let Some(i) = *found {
+            matched[i] = true;
+        }
+    }
 
-            Ok(())
+    let mut summary = SyncSummary::default();
+    let mut added = Vec::new();
+    for ((comment, name), found) in comments.iter().zip(names).zip(found) {
+        let Some(i) = found else {
+            let mut task = Task::new(&name);
+            task.tags.push(comment.keyword.to_lowercase());
+            added.push(task);
+            continue;
+        };
 
+        let task = &mut tasks[i];
+        if task.name != name {
+            task.name = name;
+            summary.moved += 1;
+        }
+        if task.checked {
+            task.checked = false;
+            task.completed = None;
+            summary.reopened += 1;
+        }
+    }
 
+    for (task, _) in tasks.iter_mut().zip(&matched).filter(|(_, matched)| !**matched) {
+        if task_key(task).is_some() && !task.checked {
+            task.checked = true;
+            task.completed = Some(Local::now());
+            summary.checked += 1;
         }
-    } else {
-        Err(LIST_NOT_FOUND.to_string())
     }
+
+    summary.added = added.len();
+    tasks.extend(added);
+
+    if created || !summary.is_empty() {
+        save_file_data(&data);
+    }
+
+    summary
 }
 
-//TODO: todo-app check
-//Если ничего не найдено, то ищет все задания начинающиеся так же
-//(по-хорошему вообще реализовать поиск вхождений типа grep),
-pub fn check_task(task: &str, list: &str) -> Result<bool, String> {
+/// Label and `(list, task)` of every task a batch applies to, or why the
+/// line doesn't name one.
+type Targets = Vec<(String, Result<(String, String), String>)>;
+
+fn line_targets<'a>(lines: &'a [String], list: &'a str) -> impl FnOnce(&HashMap<String, Vec<Task>>) -> Targets + 'a {
+    move |data| {
+        lines
+            .iter()
+            .map(|line| {
+                let (task, task_list) = match line.split_once(':') {
+                    Some((prefix, task)) if data.contains_key(prefix.trim()) => (task.trim(), prefix.trim()),
+                    _ => (line.as_str(), list),
+                };
+                (line.to_string(), Ok((task_list.to_string(), task.to_string())))
+            })
+            .collect()
+    }
+}
+
+/// Like [`line_targets`], but the task is looked up the way single-task
+/// commands do (see [`find_task`]), or given by its 1-based position in the
+/// list as `tasks LIST` prints it. Nothing can be asked in a batch, so a
+/// line matching several tasks or only tasks in other lists fails.
+fn lookup_targets<'a>(lines: &'a [String], list: &'a str, exact: bool) -> impl FnOnce(&HashMap<String, Vec<Task>>) -> Targets + 'a {
+    move |data| {
+        line_targets(lines, list)(data)
+            .into_iter()
+            .map(|(label, target)| (label, target.and_then(|(list, task)| lookup_task(data, &task, &list, exact))))
+            .collect()
+    }
+}
+
+fn lookup_task(data: &HashMap<String, Vec<Task>>, task: &str, list: &str, exact: bool) -> Result<(String, String), String> {
+    let positioned = data
+        .get(list)
+        .filter(|tasks| !tasks.iter().any(|t| t == task))
+        .and_then(|tasks| tasks.get(task.parse::<usize>().ok()?.checked_sub(1)?));
+    if let Some(found) = positioned {
+        return Ok((list.to_string(), found.name.clone()));
+    }
+
+    match resolve_task(data, task, list, exact)? {
+        Found::Task(task) => Ok((list.to_string(), task)),
+        Found::Candidates(candidates) => Err(format!("Ambiguous task name, candidates: {}", candidates.join(", "))),
+        Found::OtherLists(lists) => Err(format!("Task not found, but it is in: {}", lists.join(", "))),
+    }
+}
+
+fn filter_targets(filter: &Filter) -> impl FnOnce(&HashMap<String, Vec<Task>>) -> Targets + '_ {
+    move |data| {
+        let mut lists = data.keys().collect::<Vec<_>>();
+        lists.sort();
+
+        lists
+            .into_iter()
+            .flat_map(|list| {
+                data[list]
+                    .iter()
+                    .filter(|task| filter.matches(list, task))
+                    .map(|task| (format!("{}: {}", list, task.name), Ok((list.to_string(), task.name.clone()))))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+fn batch<T>(
+    targets: impl FnOnce(&HashMap<String, Vec<Task>>) -> Targets,
+    action: impl FnMut(&mut HashMap<String, Vec<Task>>, &str, &str) -> Result<T, String>,
+) -> BatchResults<T> {
     let mut data = get_file_data();
 
-    let status = if let Some(tasks) = data.get_mut(list) {
-        match tasks.iter_mut().find(|t| *t == task) {
+    let results = run_batch(&mut data, targets, action);
+
+    if results.iter().any(|(_, result)| result.is_ok()) {
+        save_file_data(&data);
+    }
+
+    results
+}
+
+/// Targets for `(list, task)` pairs that are already resolved.
+fn selected_targets(tasks: &[(String, String)]) -> impl FnOnce(&HashMap<String, Vec<Task>>) -> Targets + '_ {
+    |_| tasks.iter().map(|(list, task)| (format!("{}: {}", list, task), Ok((list.clone(), task.clone())))).collect()
+}
+
+/// Removes the targets, moving them to the trash.
+fn remove_batch(targets: impl FnOnce(&HashMap<String, Vec<Task>>) -> Targets) -> BatchResults<()> {
+    let mut data = get_file_data();
+    let mut trash = get_trash_data();
+
+    let results = run_batch(&mut data, targets, |data, task, list| {
+        let task = take_task(data, task, list)?;
+        trash.push(TrashedItem::new(Trashed::Task { list: list.to_string(), task }));
+        Ok(())
+    });
+
+    if results.iter().any(|(_, result)| result.is_ok()) {
+        save_files(&data, None, Some(&trash));
+    }
+
+    results
+}
+
+fn run_batch<T>(
+    data: &mut HashMap<String, Vec<Task>>,
+    targets: impl FnOnce(&HashMap<String, Vec<Task>>) -> Targets,
+    mut action: impl FnMut(&mut HashMap<String, Vec<Task>>, &str, &str) -> Result<T, String>,
+) -> BatchResults<T> {
+    targets(data)
+        .into_iter()
+        .map(|(label, target)| {
+            let result = target.and_then(|(list, task)| action(data, &task, &list));
+            (label, result)
+        })
+        .collect()
+}
+
+fn insert_task(data: &mut HashMap<String, Vec<Task>>, task: Task, list: &str) -> Result<(), String> {
+    match data.get_mut(list) {
+        Some(tasks) if tasks.contains(&task) => Err("Task already exists".to_string()),
+        Some(tasks) => {
+            tasks.push(task);
+            Ok(())
+        }
+        None => Err(LIST_NOT_FOUND.to_string()),
+    }
+}
+
+fn take_task(data: &mut HashMap<String, Vec<Task>>, task: &str, list: &str) -> Result<Task, String> {
+    match data.get_mut(list) {
+        Some(tasks) => match tasks.iter().position(|t| t == task) {
+            Some(idx) => Ok(tasks.remove(idx)),
+            None => Err(TASK_NOT_FOUND.to_string()),
+        },
+        None => Err(LIST_NOT_FOUND.to_string()),
+    }
+}
+
+fn toggle_task(data: &mut HashMap<String, Vec<Task>>, task: &str, list: &str) -> Result<bool, String> {
+    match data.get_mut(list) {
+        Some(tasks) => match tasks.iter_mut().find(|t| *t == task) {
             Some(task) => {
                 task.checked = !task.checked;
+                task.completed = if task.checked { Some(Local::now()) } else { None };
                 Ok(task.checked)
             }
-            None => {
-                let lists = match get_from_all_tasks(&mut data, task, list, "check") {
-                    Ok(lists) => lists,
-                    Err(e) => return Err(e),
-                };
+            None => Err(TASK_NOT_FOUND.to_string()),
+        },
+        None => Err(LIST_NOT_FOUND.to_string()),
+    }
+}
 
-                for list in lists {
-                    data.entry(list).and_modify(|u| {
-                        let task = u.iter_mut().find(|t| *t == task).unwrap();
-                        task.checked = !task.checked;
-                    });
-                }
+#[derive(Debug, PartialEq)]
+pub enum Found {
+    /// Name of the matching task in the requested list.
+    Task(String),
+    /// Several tasks in the requested list match partially, best first.
+    Candidates(Vec<String>),
+    /// Other lists that have a task with exactly this name.
+    OtherLists(Vec<String>),
+}
+
+/// Looks up the task the user meant in `list`.
+///
+/// An exact match always wins, then an exact match in the other lists.
+/// Unless `exact` is set, a unique prefix match is taken right away and
+/// otherwise partial matches are ranked with the fuzzy scorer. Prefix and
+/// fuzzy matching ignore case.
+pub fn find_task(task: &str, list: &str, exact: bool) -> Result<Found, String> {
+    resolve_task(&get_file_data(), task, list, exact)
+}
 
-                save_file_data(&data);
+fn resolve_task(data: &HashMap<String, Vec<Task>>, task: &str, list: &str, exact: bool) -> Result<Found, String> {
+    let tasks = match data.get(list) {
+        Some(tasks) => tasks,
+        None => return Err(LIST_NOT_FOUND.to_string()),
+    };
+
+    if tasks.iter().any(|t| t == task) {
+        return Ok(Found::Task(task.to_string()));
+    }
+
+    let mut lists = data.iter()
+        .filter(|(other, tasks)| *other != list && tasks.iter().any(|t| t == task))
+        .map(|(other, _)| other.to_string())
+        .collect::<Vec<_>>();
+    lists.sort();
+
+    if !lists.is_empty() {
+        return Ok(Found::OtherLists(lists));
+    }
+
+    if !exact {
+        let prefix = task.to_lowercase();
+        let prefixed = tasks.iter().filter(|t| t.name.to_lowercase().starts_with(&prefix)).collect::<Vec<_>>();
+        if prefixed.len() == 1 {
+            return Ok(Found::Task(prefixed[0].name.clone()));
+        }
 
-                Ok(true)
+        let candidates = fuzzy_rank(task, tasks.iter().map(|t| t.name.as_str()));
+        if !candidates.is_empty() {
+            return Ok(Found::Candidates(candidates));
+        }
+    }
+
+    Err(TASK_NOT_FOUND.to_string())
+}
+
+/// A checked task moved out of its list by [`clean`].
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ArchivedTask {
+    pub list: String,
+    #[serde(flatten)]
+    pub task: Task,
+}
+
+fn get_archive_data() -> Vec<ArchivedTask> {
+    let mut file_dir = get_dir();
+    file_dir.push("archive.json");
+    if !file_dir.exists() {
+        return Vec::new();
+    }
+
+    serde_json::from_str(
+        std::fs::read_to_string(&file_dir)
+            .expect("Unable to read file")
+            .as_str()
+    )
+    .expect("Unable to deserialize json")
+}
+
+fn write_archive_data(archive: &[ArchivedTask]) {
+    let mut file_dir = get_dir();
+    file_dir.push("archive.json");
+    let file = File::create(&file_dir).expect("Unable to create file");
+    serde_json::to_writer(file, archive).expect("Unable to write to file");
+}
+
+/// Moves the checked tasks of `list`, or of every list, to the archive and
+/// returns them. Tasks checked before completion dates were recorded are
+/// stamped with the current time.
+pub fn clean(list: Option<&str>) -> Result<Vec<(String, Task)>, String> {
+    let mut data = get_file_data();
+
+    if let Some(list) = list {
+        if !data.contains_key(list) {
+            return Err(LIST_NOT_FOUND.to_string());
+        }
+    }
+
+    let mut lists = data.keys().filter(|l| list.is_none() || list == Some(l.as_str())).cloned().collect::<Vec<_>>();
+    lists.sort();
+
+    let mut cleaned = Vec::new();
+    for list in lists {
+        let tasks = data.get_mut(&list).unwrap();
+        let (checked, open) = std::mem::take(tasks).into_iter().partition::<Vec<_>, _>(|task| task.checked);
+        *tasks = open;
+        cleaned.extend(checked.into_iter().map(|mut task| {
+            task.completed.get_or_insert_with(Local::now);
+            (list.clone(), task)
+        }));
+    }
+
+    if !cleaned.is_empty() {
+        let mut archive = get_archive_data();
+        archive.extend(cleaned.iter().map(|(list, task)| ArchivedTask { list: list.clone(), task: task.clone() }));
+        save_files(&data, Some(&archive), None);
+    }
+
+    Ok(cleaned)
+}
+
+/// Archived tasks, oldest first.
+pub fn get_archive() -> Vec<(String, Task)> {
+    get_archive_data().into_iter().map(|archived| (archived.list, archived.task)).collect()
+}
+
+/// Puts the most recently archived task with this name back into the list
+/// it came from, creating the list if it was removed since. With `list`,
+/// only tasks archived from that list are considered. Returns the list.
+pub fn restore_archived(task: &str, list: Option<&str>) -> Result<String, String> {
+    let mut archive = get_archive_data();
+
+    let idx = archive
+        .iter()
+        .rposition(|archived| archived.task == *task && (list.is_none() || list == Some(archived.list.as_str())))
+        .ok_or_else(|| TASK_NOT_FOUND.to_string())?;
+
+    let mut data = get_file_data();
+    let archived = archive.remove(idx);
+    data.entry(archived.list.clone()).or_default();
+    insert_task(&mut data, archived.task, &archived.list)?;
+
+    save_files(&data, Some(&archive), None);
+
+    Ok(archived.list)
+}
+
+/// A removed task or list, kept until the trash is emptied or purged.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TrashedItem {
+    pub deleted: DateTime<Local>,
+    #[serde(flatten)]
+    pub item: Trashed,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Trashed {
+    Task { list: String, task: Task },
+    List { list: String, tasks: Vec<Task> },
+}
+
+impl TrashedItem {
+    fn new(item: Trashed) -> Self {
+        Self { deleted: Local::now(), item }
+    }
+
+    /// Whether `item` names this entry: a list name, a task name or
+    /// `list: task`.
+    pub fn is(&self, item: &str) -> bool {
+        match &self.item {
+            Trashed::Task { list, task } => {
+                task.name == item || item.strip_prefix(list.as_str()).and_then(|rest| rest.strip_prefix(':'))
+                    .is_some_and(|rest| rest.trim() == task.name)
             }
+            Trashed::List { list, .. } => list == item,
         }
+    }
+
+    /// Whether this entry is older than `days`, see [`trash_days`].
+    fn expired(&self, days: u32) -> bool {
+        days > 0 && self.deleted < Local::now() - Duration::days(days.into())
+    }
+}
+
+/// `trash_days` from the config, read once per operation.
+fn trash_days() -> u32 {
+    crate::config::get_config().map(|config| config.trash_days).unwrap_or_default()
+}
+
+/// The trash as it is on disk, expired entries included.
+fn get_trash_data() -> Vec<TrashedItem> {
+    let mut file_dir = get_dir();
+    file_dir.push("trash.json");
+    if !file_dir.exists() {
+        return Vec::new();
+    }
+
+    serde_json::from_str(
+        std::fs::read_to_string(&file_dir)
+            .expect("Unable to read file")
+            .as_str()
+    )
+    .expect("Unable to deserialize json")
+}
+
+fn write_trash_data(trash: &[TrashedItem]) {
+    let mut file_dir = get_dir();
+    file_dir.push("trash.json");
+    let file = File::create(&file_dir).expect("Unable to create file");
+    serde_json::to_writer(file, trash).expect("Unable to write to file");
+}
+
+/// Trashed tasks and lists, oldest first, without the expired ones. Those
+/// are dropped with the next change to the trash.
+pub fn get_trash() -> Vec<TrashedItem> {
+    let days = trash_days();
+    get_trash_data().into_iter().filter(|trashed| !trashed.expired(days)).collect()
+}
+
+/// Puts the most recently trashed item matching `item` (see
+/// [`TrashedItem::is`]) back. A task goes to its list, which is created
+/// again if needed.
+pub fn restore_trashed(item: &str) -> Result<TrashedItem, String> {
+    let mut trash = get_trash_data();
+    let days = trash_days();
+    let idx = trash.iter().rposition(|trashed| trashed.is(item) && !trashed.expired(days)).ok_or_else(|| "Not found in the trash".to_string())?;
+
+    let mut data = get_file_data();
+    let trashed = trash.remove(idx);
+    match trashed.item.clone() {
+        Trashed::Task { list, task } => {
+            data.entry(list.clone()).or_default();
+            insert_task(&mut data, task, &list)?;
+        }
+        Trashed::List { list, tasks } => {
+            if data.contains_key(&list) {
+                return Err("List already exists".to_string());
+            }
+            data.insert(list, tasks);
+        }
+    }
+
+    save_files(&data, None, Some(&trash));
+
+    Ok(trashed)
+}
+
+/// Deletes everything in the trash for good and returns how many entries
+/// there were.
+pub fn empty_trash() -> usize {
+    let count = get_trash().len();
+    if !get_trash_data().is_empty() {
+        save_files(&get_file_data(), None, Some(&[]));
+    }
+    count
+}
+
+fn get_journal() -> Journal {
+    let mut file_dir = get_dir();
+    file_dir.push("journal.json");
+    if !file_dir.exists() {
+        return Journal::default();
+    }
+
+    serde_json::from_str(
+        std::fs::read_to_string(&file_dir)
+            .expect("Unable to read file")
+            .as_str()
+    )
+    .expect("Unable to deserialize json")
+}
+
+fn save_journal(journal: &Journal) {
+    let mut file_dir = get_dir();
+    file_dir.push("journal.json");
+    let file = File::create(&file_dir).expect("Unable to create file");
+    serde_json::to_writer(file, journal).expect("Unable to write to file");
+}
+
+/// Adds the changes to the history and to the journal as one operation.
+fn record(changes: Vec<Change>) {
+    append_history(&changes);
+
+    let mut journal = get_journal();
+    journal.record(changes);
+    save_journal(&journal);
+}
+
+fn append_history<'a>(changes: impl IntoIterator<Item = &'a Change>) {
+    let time = Local::now();
+    let lines = changes
+        .into_iter()
+        .flat_map(|change| change.history(time))
+        .map(|entry| serde_json::to_string(&entry).expect("Unable to serialize history") + "\n")
+        .collect::<String>();
+
+    if lines.is_empty() {
+        return;
+    }
+
+    let mut file_dir = get_dir();
+    file_dir.push("history.jsonl");
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&file_dir)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, lines.as_bytes()))
+        .expect("Unable to write to file");
+}
+
+/// Every change ever made, oldest first.
+pub fn get_history() -> Vec<HistoryEntry> {
+    let mut file_dir = get_dir();
+    file_dir.push("history.jsonl");
+    if !file_dir.exists() {
+        return Vec::new();
+    }
+
+    std::fs::read_to_string(&file_dir)
+        .expect("Unable to read file")
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).expect("Unable to deserialize json"))
+        .collect()
+}
+
+/// Reverts the last `count` operations and returns them, latest first.
+/// Nothing is changed if one of them can't be reverted.
+pub fn undo(count: usize) -> Result<Vec<Operation>, String> {
+    replay(count, true)
+}
+
+/// Applies the last `count` undone operations again and returns them.
+pub fn redo(count: usize) -> Result<Vec<Operation>, String> {
+    replay(count, false)
+}
+
+fn replay(count: usize, undo: bool) -> Result<Vec<Operation>, String> {
+    let mut journal = get_journal();
+    let mut files = Files { tasks: get_file_data(), archive: get_archive_data(), trash: get_trash_data() };
+
+    let (from, to) = if undo {
+        (&mut journal.done, &mut journal.undone)
     } else {
-        Err(LIST_NOT_FOUND.to_string())
+        (&mut journal.undone, &mut journal.done)
     };
 
-    save_file_data(&data);
+    if from.is_empty() {
+        return Err(format!("Nothing to {}", if undo { "undo" } else { "redo" }));
+    }
 
-    status
-}
+    let mut replayed = Vec::new();
+    for operation in from.iter().rev().take(count) {
+        let result = if undo {
+            operation.undo(&mut files)
+        } else {
+            operation.redo(&mut files)
+        };
+        result.map_err(|e| format!("Unable to {}: {}", if undo { "undo" } else { "redo" }, e))?;
+        replayed.push(operation.clone());
+    }
 
+    if undo {
+        let inverse = replayed.iter().flat_map(|op| op.changes.iter().rev().map(Change::inverse)).collect::<Vec<_>>();
+        append_history(&inverse);
+    } else {
+        append_history(replayed.iter().flat_map(|op| &op.changes));
+    }
+
+    from.truncate(from.len() - replayed.len());
+    to.extend(replayed.iter().cloned());
+
+    write_file_data(&files.tasks);
+    if replayed.iter().any(Operation::touches_archive) {
+        write_archive_data(&files.archive);
+    }
+    if replayed.iter().any(Operation::touches_trash) {
+        write_trash_data(&files.trash);
+    }
+    save_journal(&journal);
+
+    Ok(replayed)
+}
 
 pub fn get_tasks(list: Option<&str>) -> Result<Vec<Task>, String> {
     let data = get_file_data();
@@ -216,39 +944,159 @@ pub fn get_tasks(list: Option<&str>) -> Result<Vec<Task>, String> {
     }
 }
 
-pub fn check_dir() {
-    let mut file_dir = get_dir();
+pub fn search_tasks(matcher: &Matcher) -> Vec<(String, Task)> {
+    collect_tasks(|_, task| matcher.is_match(&task.name))
+}
 
-    if !file_dir.exists() {
-        std::fs::create_dir(&file_dir).expect("Unable to create directory");
-    }
+pub fn get_all_tasks() -> Vec<(String, Task)> {
+    collect_tasks(|_, _| true)
+}
 
-    file_dir.push("config.toml");
+pub fn filter_tasks(filter: &Filter) -> Vec<(String, Task)> {
+    collect_tasks(|list, task| filter.matches(list, task))
+}
 
-    if !file_dir.exists() {
-        let config = crate::config::Settings::default();
+fn collect_tasks(predicate: impl Fn(&str, &Task) -> bool) -> Vec<(String, Task)> {
+    let data = get_file_data();
+
+    let mut lists = data.keys().collect::<Vec<_>>();
+    lists.sort();
+
+    lists.into_iter().flat_map(|list| {
+        data[list]
+            .iter()
+            .filter(|task| predicate(list, task))
+            .map(|task| (list.to_string(), task.clone()))
+            .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+/// Creates the config in `~/.todo-app`, picks the store the commands will
+/// use (see [`open_store`]) and creates it if needed.
+pub fn check_dir(global: bool) {
+    let home = home_store();
+    std::fs::create_dir_all(&home).expect("Unable to create directory");
+
+    let config = home.join("config.toml");
+    if !config.exists() {
+        let settings = crate::config::Settings::default();
         std::fs::write(
-            &file_dir,
-            toml::to_string(&config).expect("Failed to serialize config")
+            &config,
+            toml::to_string(&settings).expect("Failed to serialize config")
         ).expect("Unable to create file");
     }
-    file_dir.pop();
 
-    file_dir.push("tasks.json");
+    let store = STORE.get_or_init(|| open_store(global));
+    std::fs::create_dir_all(&store.dir).expect("Unable to create directory");
 
-    if !file_dir.exists() {
-        std::fs::write(file_dir, "{}").expect("Unable to create file");
+    // An empty `.todo.json` is a fresh project store.
+    if std::fs::read_to_string(&store.tasks).map_or(true, |content| content.trim().is_empty()) {
+        std::fs::write(&store.tasks, "{}").expect("Unable to create file");
     }
+}
 
+/// Where the tasks and the files next to them (archive, trash, journal,
+/// history and backups) are kept.
+struct Store {
+    dir: PathBuf,
+    tasks: PathBuf,
+}
 
+static STORE: OnceLock<Store> = OnceLock::new();
 
+fn store() -> &'static Store {
+    STORE.get_or_init(|| open_store(false))
 }
 
-fn get_dir() -> PathBuf {
+fn home_store() -> PathBuf {
     let mut file_dir = match dirs::home_dir() {
-        Some(path) => PathBuf::from(path),
+        Some(path) => path,
         None => PathBuf::from("/tmp"),
     };
     file_dir.push(".todo-app");
     file_dir
-}
\ No newline at end of file
+}
+
+/// The store of the nearest directory, from the working directory up, with
+/// a `.todo/` or a `.todo.json` in it, or `~/.todo-app` outside of projects
+/// and with `global`.
+///
+/// A `.todo/` holds the whole store. A `.todo.json` is only the tasks, meant
+/// to be committed; the other files go to `~/.todo-app/projects/<id>` so
+/// they stay out of the repository.
+fn open_store(global: bool) -> Store {
+    let home = home_store();
+    let project = std::env::current_dir().ok().filter(|_| !global).and_then(|cwd| {
+        cwd.ancestors()
+            .find(|dir| dir.join(".todo.json").is_file() || dir.join(".todo").is_dir())
+            .map(|dir| dir.to_path_buf())
+    });
+
+    match project {
+        Some(project) if project.join(".todo.json").is_file() => {
+            let path = project.canonicalize().unwrap_or_else(|_| project.clone());
+            let id = Uuid::new_v5(&Uuid::NAMESPACE_URL, format!("file://{}", path.display()).as_bytes());
+            Store { dir: home.join("projects").join(id.to_string()), tasks: project.join(".todo.json") }
+        }
+        Some(project) => Store { dir: project.join(".todo"), tasks: project.join(".todo/tasks.json") },
+        None => Store { tasks: home.join("tasks.json"), dir: home },
+    }
+}
+
+/// Directory of the store, see [`open_store`].
+pub(crate) fn get_dir() -> PathBuf {
+    store().dir.clone()
+}
+
+/// `.todo.json` if the project has one, `tasks.json` in the store otherwise.
+pub(crate) fn tasks_file() -> PathBuf {
+    store().tasks.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(lists: &[(&str, &[&str])]) -> HashMap<String, Vec<Task>> {
+        lists
+            .iter()
+            .map(|(list, tasks)| (list.to_string(), tasks.iter().map(|name| Task::new(name)).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn resolve_task_prefers_exact_matches() {
+        let data = data(&[("work", &["milkshake", "mail bob"]), ("shopping", &["milk"])]);
+
+        assert_eq!(resolve_task(&data, "mail bob", "work", false), Ok(Found::Task("mail bob".to_string())));
+        assert_eq!(resolve_task(&data, "milk", "work", false), Ok(Found::OtherLists(vec!["shopping".to_string()])));
+        assert_eq!(resolve_task(&data, "milk", "work", true), Ok(Found::OtherLists(vec!["shopping".to_string()])));
+    }
+
+    #[test]
+    fn lookup_task_takes_positions() {
+        let data = data(&[("work", &["mail bob", "2", "make tea"]), ("home", &["milk"])]);
+
+        assert_eq!(lookup_task(&data, "1", "work", false), Ok(("work".to_string(), "mail bob".to_string())));
+        assert_eq!(lookup_task(&data, "2", "work", false), Ok(("work".to_string(), "2".to_string())));
+        assert_eq!(lookup_task(&data, "3", "work", true), Ok(("work".to_string(), "make tea".to_string())));
+        assert_eq!(lookup_task(&data, "0", "work", true), Err(TASK_NOT_FOUND.to_string()));
+        assert_eq!(lookup_task(&data, "ma", "work", false), Err("Ambiguous task name, candidates: mail bob, make tea".to_string()));
+        assert_eq!(lookup_task(&data, "milk", "work", false), Err("Task not found, but it is in: home".to_string()));
+    }
+
+    #[test]
+    fn resolve_task_matches_prefixes_and_fuzzy_ignoring_case() {
+        let data = data(&[("work", &["Milkshake", "mail bob", "make tea"])]);
+
+        assert_eq!(resolve_task(&data, "MILK", "work", false), Ok(Found::Task("Milkshake".to_string())));
+        assert_eq!(
+            resolve_task(&data, "MA", "work", false),
+            Ok(Found::Candidates(vec!["mail bob".to_string(), "make tea".to_string(), "Milkshake".to_string()]))
+        );
+        assert_eq!(resolve_task(&data, "milk", "work", true), Err(TASK_NOT_FOUND.to_string()));
+        assert_eq!(resolve_task(&data, "zzz", "work", false), Err(TASK_NOT_FOUND.to_string()));
+        assert_eq!(resolve_task(&data, "milk", "home", false), Err(LIST_NOT_FOUND.to_string()));
+    }
+}
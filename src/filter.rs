@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::files::{parse_date, Task};
+
+/// Saved queries by name, see [`Filter::parse_with_views`].
+pub type Views = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Checked(bool),
+    Due(Op, NaiveDate),
+    HasDue(bool),
+    Tag(String),
+    List(String),
+    Text(String),
+}
+
+impl Filter {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        Self::parse_with_views(query, &Views::new())
+    }
+
+    /// Like [`parse`](Filter::parse), with `view:NAME` standing for the
+    /// saved query `NAME`.
+    pub fn parse_with_views(query: &str, views: &Views) -> Result<Self, String> {
+        parse_query(query, views, &mut Vec::new())
+    }
+
+    pub fn matches(&self, list: &str, task: &Task) -> bool {
+        match self {
+            Filter::And(a, b) => a.matches(list, task) && b.matches(list, task),
+            Filter::Or(a, b) => a.matches(list, task) || b.matches(list, task),
+            Filter::Not(f) => !f.matches(list, task),
+            Filter::Checked(checked) => task.checked == *checked,
+            Filter::Due(op, date) => match task.due {
+                Some(due) => match op {
+                    Op::Eq => due == *date,
+                    Op::Lt => due < *date,
+                    Op::Le => due <= *date,
+                    Op::Gt => due > *date,
+                    Op::Ge => due >= *date,
+                },
+                None => false,
+            },
+            Filter::HasDue(has) => task.due.is_some() == *has,
+            Filter::Tag(tag) => task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Filter::List(name) => list == name,
+            Filter::Text(text) => task.name.to_lowercase().contains(&text.to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(String),
+    Text(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::And => write!(f, "'and'"),
+            Token::Or => write!(f, "'or'"),
+            Token::Not => write!(f, "'not'"),
+            Token::Term(term) => write!(f, "'{}'", term),
+            Token::Text(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Text(read_quoted(&mut chars)?));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c == '"' {
+                        word += &read_quoted(&mut chars)?;
+                    } else {
+                        word.push(c);
+                    }
+                }
+                tokens.push(match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Term(word),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    let mut text = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(text);
+        }
+        text.push(c);
+    }
+    Err("Unterminated quote in filter".to_string())
+}
+
+/// `open` holds the views being expanded, to catch views using themselves.
+fn parse_query(query: &str, views: &Views, open: &mut Vec<String>) -> Result<Filter, String> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser { tokens, pos: 0, views, open };
+
+    let filter = parser.or_expr()?;
+
+    match parser.peek() {
+        None => Ok(filter),
+        Some(token) => Err(format!("Unexpected {} in filter", token)),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    views: &'a Views,
+    open: &'a mut Vec<String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or_expr(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and_expr()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            filter = Filter::Or(Box::new(filter), Box::new(self.and_expr()?));
+        }
+        Ok(filter)
+    }
+
+    fn and_expr(&mut self) -> Result<Filter, String> {
+        let mut filter = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Or) | Some(Token::RParen) | None => return Ok(filter),
+                _ => {}
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Filter::Not(Box::new(self.unary()?))),
+            Some(Token::LParen) => {
+                let filter = self.or_expr()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(filter),
+                    _ => Err("Missing ')' in filter".to_string()),
+                }
+            }
+            Some(Token::Term(term)) => match term.strip_prefix("view:") {
+                Some(name) => self.view(name),
+                None => parse_term(&term),
+            },
+            Some(Token::Text(text)) => Ok(Filter::Text(text)),
+            Some(token) => Err(format!("Unexpected {} in filter", token)),
+            None => Err("Unexpected end of filter".to_string()),
+        }
+    }
+
+    fn view(&mut self, name: &str) -> Result<Filter, String> {
+        let query = self.views.get(name).ok_or_else(|| format!("Unknown view: {}", name))?;
+        if self.open.iter().any(|open| open == name) {
+            return Err(format!("View {} uses itself", name));
+        }
+
+        self.open.push(name.to_string());
+        let filter = parse_query(query, self.views, self.open).map_err(|e| format!("View {}: {}", name, e));
+        self.open.pop();
+        filter
+    }
+}
+
+fn parse_term(term: &str) -> Result<Filter, String> {
+    let idx = match term.find([':', '=', '<', '>']) {
+        Some(idx) => idx,
+        None => return Ok(Filter::Text(term.to_string())),
+    };
+
+    let (key, rest) = term.split_at(idx);
+    let (op, value) = if let Some(value) = rest.strip_prefix("<=") {
+        (Op::Le, value)
+    } else if let Some(value) = rest.strip_prefix(">=") {
+        (Op::Ge, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Op::Lt, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Op::Gt, value)
+    } else {
+        (Op::Eq, &rest[1..])
+    };
+
+    if op != Op::Eq && key != "due" {
+        return Err(format!("'{}' can only be compared with ':'", key));
+    }
+
+    match key {
+        "status" => match value {
+            "open" | "todo" | "unchecked" => Ok(Filter::Checked(false)),
+            "done" | "checked" => Ok(Filter::Checked(true)),
+            _ => Err(format!("Unknown status: {}", value)),
+        },
+        "due" => match value {
+            "none" if op == Op::Eq => Ok(Filter::HasDue(false)),
+            "any" if op == Op::Eq => Ok(Filter::HasDue(true)),
+            _ => Ok(Filter::Due(op, parse_date(value)?)),
+        },
+        "tag" => Ok(Filter::Tag(value.to_string())),
+        "list" => Ok(Filter::List(value.to_string())),
+        "text" | "name" => Ok(Filter::Text(value.to_string())),
+        _ => Err(format!("Unknown filter key: {}", key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Box<Filter> {
+        Box::new(Filter::Text(text.to_string()))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            Filter::parse("a or b c").unwrap(),
+            Filter::Or(text("a"), Box::new(Filter::And(text("b"), text("c")))),
+        );
+        assert_eq!(
+            Filter::parse("a and b or c").unwrap(),
+            Filter::Or(Box::new(Filter::And(text("a"), text("b"))), text("c")),
+        );
+    }
+
+    #[test]
+    fn not_binds_tightest_and_parentheses_group() {
+        assert_eq!(
+            Filter::parse("not a and b").unwrap(),
+            Filter::And(Box::new(Filter::Not(text("a"))), text("b")),
+        );
+        assert_eq!(
+            Filter::parse("not (a or b)").unwrap(),
+            Filter::Not(Box::new(Filter::Or(text("a"), text("b")))),
+        );
+        assert_eq!(
+            Filter::parse("(a or b) and c").unwrap(),
+            Filter::And(Box::new(Filter::Or(text("a"), text("b"))), text("c")),
+        );
+    }
+
+    #[test]
+    fn parses_terms() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 20).unwrap();
+        assert_eq!(Filter::parse("status:done").unwrap(), Filter::Checked(true));
+        assert_eq!(Filter::parse("due<=2026-10-20").unwrap(), Filter::Due(Op::Le, date));
+        assert_eq!(Filter::parse("due:none").unwrap(), Filter::HasDue(false));
+        assert_eq!(Filter::parse("tag:\"a b\"").unwrap(), Filter::Tag("a b".to_string()));
+        assert_eq!(Filter::parse("\"and\"").unwrap(), *text("and"));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = |query: &str| Filter::parse(query).unwrap_err();
+        assert_eq!(error("(a or b"), "Missing ')' in filter");
+        assert_eq!(error("a)"), "Unexpected ')' in filter");
+        assert_eq!(error("a and"), "Unexpected end of filter");
+        assert_eq!(error("or a"), "Unexpected 'or' in filter");
+        assert_eq!(error("\"a"), "Unterminated quote in filter");
+        assert_eq!(error("size:3"), "Unknown filter key: size");
+        assert_eq!(error("status:later"), "Unknown status: later");
+        assert_eq!(error("tag>a"), "'tag' can only be compared with ':'");
+        assert_eq!(error("due<someday"), "Invalid date: someday");
+    }
+
+    #[test]
+    fn expands_views() {
+        let views = Views::from([
+            ("open".to_string(), "status:open".to_string()),
+            ("work".to_string(), "view:open and list:work".to_string()),
+            ("loop".to_string(), "a or view:loop".to_string()),
+        ]);
+
+        assert_eq!(
+            Filter::parse_with_views("view:work or b", &views).unwrap(),
+            Filter::Or(
+                Box::new(Filter::And(Box::new(Filter::Checked(false)), Box::new(Filter::List("work".to_string())))),
+                text("b"),
+            ),
+        );
+        assert_eq!(Filter::parse_with_views("view:nope", &views).unwrap_err(), "Unknown view: nope");
+        assert_eq!(Filter::parse_with_views("view:loop", &views).unwrap_err(), "View loop: View loop uses itself");
+        assert_eq!(Filter::parse("view:open").unwrap_err(), "Unknown view: open");
+    }
+
+    #[test]
+    fn matches_tasks() {
+        let mut task = Task::new("Write Report");
+        task.tags.push("Work".to_string());
+        task.due = NaiveDate::from_ymd_opt(2026, 10, 20);
+
+        let matches = |query: &str| Filter::parse(query).unwrap().matches("inbox", &task);
+        assert!(matches("report and tag:work and list:inbox"));
+        assert!(matches("due>2026-10-19 and due<=2026-10-20 and due:any"));
+        assert!(!matches("status:done or list:work"));
+        assert!(matches("not (status:done or due:none)"));
+    }
+}
@@ -0,0 +1,197 @@
+//! CSV with a header row. Which column holds which task field is set with a
+//! mapping like `name=Task,checked=Done,list=Project`; without one, the
+//! columns are named after the fields. Tags are joined with `;`, dates are
+//! `YYYY-MM-DD` and times RFC 3339.
+
+use chrono::{DateTime, Local};
+
+use crate::files::{parse_date, parse_priority, Task};
+
+use super::start_of_day;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    List,
+    Name,
+    Checked,
+    Due,
+    Tags,
+    Priority,
+    Created,
+    Completed,
+}
+
+const FIELDS: [(&str, Field); 8] = [
+    ("list", Field::List),
+    ("name", Field::Name),
+    ("checked", Field::Checked),
+    ("due", Field::Due),
+    ("tags", Field::Tags),
+    ("priority", Field::Priority),
+    ("created", Field::Created),
+    ("completed", Field::Completed),
+];
+
+/// `(header, field)` pairs from a mapping, or every field under its own
+/// name without one.
+fn columns(mapping: Option<&str>) -> Result<Vec<(String, Field)>, String> {
+    let mapping = match mapping {
+        Some(mapping) => mapping,
+        None => return Ok(FIELDS.iter().map(|(name, field)| (name.to_string(), *field)).collect()),
+    };
+
+    mapping
+        .split(',')
+        .map(|column| {
+            let (field, header) = column.split_once('=').unwrap_or((column, column));
+            FIELDS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(field.trim()))
+                .map(|(_, field)| (header.trim().to_string(), *field))
+                .ok_or_else(|| format!("Unknown task field: {}", field.trim()))
+        })
+        .collect()
+}
+
+pub fn parse(text: &str, default_list: &str, mapping: Option<&str>) -> Result<Vec<(String, Task)>, String> {
+    let columns = columns(mapping)?;
+    let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+
+    let fields = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|header| {
+            columns
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(header.trim()))
+                .map(|(_, field)| *field)
+        })
+        .collect::<Vec<_>>();
+
+    if !fields.contains(&Some(Field::Name)) {
+        return Err("No column for the task name".to_string());
+    }
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record.map_err(|e| e.to_string())?;
+            parse_record(&record, &fields, default_list).map_err(|e| format!("Row {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+fn parse_record(record: &::csv::StringRecord, fields: &[Option<Field>], default_list: &str) -> Result<(String, Task), String> {
+    let mut task = Task::new("");
+    task.created = None;
+    let mut list = default_list.to_string();
+
+    for (raw, field) in record.iter().zip(fields) {
+        let value = raw.trim();
+        let Some(field) = field else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+
+        match field {
+            Field::List => list = value.to_string(),
+            // Names are kept as they are, spaces included.
+            Field::Name => task.name = raw.to_string(),
+            Field::Checked => {
+                task.checked = match value.to_lowercase().as_str() {
+                    "true" | "yes" | "y" | "x" | "1" | "done" => true,
+                    "false" | "no" | "n" | "0" | "open" => false,
+                    _ => return Err(format!("Invalid checked value: {}", value)),
+                }
+            }
+            Field::Due => task.due = Some(parse_date(value)?),
+            Field::Tags => task.tags = value.split(';').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect(),
+            Field::Priority => task.priority = Some(parse_priority(value)?),
+            Field::Created => task.created = Some(parse_time(value)?),
+            Field::Completed => task.completed = Some(parse_time(value)?),
+        }
+    }
+
+    if task.name.is_empty() {
+        return Err("Task without a name".to_string());
+    }
+
+    Ok((list, task))
+}
+
+/// RFC 3339 or a plain date.
+fn parse_time(value: &str) -> Result<DateTime<Local>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Local))
+        .or_else(|_| parse_date(value).and_then(|date| start_of_day(date).ok_or_else(|| format!("Invalid date: {}", value))))
+}
+
+pub fn write(tasks: &[(String, Task)], mapping: Option<&str>) -> Result<String, String> {
+    let columns = columns(mapping)?;
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(columns.iter().map(|(header, _)| header)).map_err(|e| e.to_string())?;
+    for (list, task) in tasks {
+        let record = columns.iter().map(|(_, field)| match field {
+            Field::List => list.clone(),
+            Field::Name => task.name.clone(),
+            Field::Checked => task.checked.to_string(),
+            Field::Due => task.due.map(|due| due.to_string()).unwrap_or_default(),
+            Field::Tags => task.tags.join(";"),
+            Field::Priority => task.priority.map(|p| p.to_string()).unwrap_or_default(),
+            Field::Created => task.created.map(|c| c.to_rfc3339()).unwrap_or_default(),
+            Field::Completed => task.completed.map(|c| c.to_rfc3339()).unwrap_or_default(),
+        });
+        writer.write_record(record).map_err(|e| e.to_string())?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting_round_trips() {
+        let mut task = Task::new("say \"hi\", then\nleave");
+        task.tags = vec!["a,b".to_string(), "c".to_string()];
+        let tasks = vec![("my, list".to_string(), task)];
+
+        let text = write(&tasks, Some("list,name,tags")).unwrap();
+        assert_eq!(text, "list,name,tags\n\"my, list\",\"say \"\"hi\"\", then\nleave\",\"a,b;c\"\n");
+
+        let parsed = parse(&text, "default", Some("list,name,tags")).unwrap();
+        assert_eq!(parsed[0].0, "my, list");
+        assert_eq!(parsed[0].1.name, "say \"hi\", then\nleave");
+        assert_eq!(parsed[0].1.tags, ["a,b", "c"]);
+
+        let text = write(&[("a".to_string(), Task::new("  indented "))], Some("name")).unwrap();
+        assert_eq!(parse(&text, "default", None).unwrap()[0].1.name, "  indented ");
+    }
+
+    #[test]
+    fn maps_columns_by_header() {
+        let text = "Done,Extra,Task,Project\nyes,ignored,pay rent,home\n,,call mom,\n";
+        let tasks = parse(text, "inbox", Some("name=Task,checked=Done,list=Project")).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!((tasks[0].0.as_str(), tasks[0].1.name.as_str(), tasks[0].1.checked), ("home", "pay rent", true));
+        assert_eq!((tasks[1].0.as_str(), tasks[1].1.name.as_str(), tasks[1].1.checked), ("inbox", "call mom", false));
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(parse("a,b\n1,2\n", "default", None), Err("No column for the task name".to_string()));
+        assert_eq!(parse("name,checked\nx,maybe\n", "default", None), Err("Row 1: Invalid checked value: maybe".to_string()));
+        assert_eq!(parse("name,due\nx,soon\n", "default", None), Err("Row 1: Invalid date: soon".to_string()));
+        assert_eq!(parse("name\n\"\"\n", "default", None), Err("Row 1: Task without a name".to_string()));
+        assert_eq!(parse("name\n\" \"\n", "default", None), Err("Row 1: Task without a name".to_string()));
+        assert_eq!(columns(Some("name,owner")), Err("Unknown task field: owner".to_string()));
+    }
+}
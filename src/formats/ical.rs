@@ -0,0 +1,250 @@
+//! iCalendar (RFC 5545) files of `VTODO` components. The list is the first
+//! category and tags are the other ones. Priorities `A` to `I` map to 1
+//! (highest) to 9, lower ones to 9.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::files::Task;
+
+use super::{start_of_day, task_uuid};
+
+pub fn write(tasks: &[(String, Task)]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//todo-app//EN".to_string(),
+    ];
+
+    let now = format_time(&Local::now());
+    for (list, task) in tasks {
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}@todo-app", task_uuid(list, task)));
+        lines.push(format!("DTSTAMP:{}", now));
+        if let Some(created) = task.created {
+            lines.push(format!("CREATED:{}", format_time(&created)));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&task.name)));
+        lines.push(format!("STATUS:{}", if task.checked { "COMPLETED" } else { "NEEDS-ACTION" }));
+        if let Some(completed) = task.completed.filter(|_| task.checked) {
+            lines.push(format!("COMPLETED:{}", format_time(&completed)));
+        }
+        let categories = std::iter::once(list).chain(&task.tags).map(|c| escape(c)).collect::<Vec<_>>();
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        if let Some(due) = task.due {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+        }
+        if let Some(priority) = task.priority {
+            lines.push(format!("PRIORITY:{}", (priority as u8 - b'A' + 1).min(9)));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+fn format_time(time: &DateTime<Local>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Splits lines longer than 75 bytes, continuing them with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+pub fn parse(text: &str, default_list: &str) -> Result<Vec<(String, Task)>, String> {
+    let mut tasks = Vec::new();
+    let mut current: Option<(Option<String>, Task)> = None;
+    // Depth of components inside the current VTODO, like a VALARM.
+    let mut nested = 0;
+
+    for line in unfold(text) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters like `VALUE=DATE` or `TZID=...` don't change how values are read.
+        let name = name.split(';').next().unwrap_or_default().to_uppercase();
+
+        // The properties of nested components are not the task's.
+        if current.is_some() && name == "BEGIN" {
+            nested += 1;
+            continue;
+        }
+        if nested > 0 {
+            if name == "END" {
+                nested -= 1;
+            }
+            continue;
+        }
+
+        match (name.as_str(), &mut current) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                let mut task = Task::new("");
+                task.created = None;
+                current = Some((None, task));
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => {
+                let (list, task) = current.take().unwrap();
+                if task.name.is_empty() {
+                    return Err("VTODO without a summary".to_string());
+                }
+                tasks.push((list.unwrap_or(default_list.to_string()), task));
+            }
+            ("SUMMARY", Some((_, task))) => task.name = unescape(value),
+            ("STATUS", Some((_, task))) => task.checked = value.eq_ignore_ascii_case("COMPLETED"),
+            ("COMPLETED", Some((_, task))) => {
+                task.checked = true;
+                task.completed = Some(parse_time(value)?);
+            }
+            ("CREATED", Some((_, task))) => task.created = Some(parse_time(value)?),
+            ("DUE", Some((_, task))) => task.due = Some(parse_time(value)?.date_naive()),
+            ("PRIORITY", Some((_, task))) => {
+                task.priority = match value.trim().parse::<u8>() {
+                    Ok(0) => None,
+                    Ok(priority @ 1..=9) => Some((b'A' + priority - 1) as char),
+                    _ => return Err(format!("Invalid priority: {}", value)),
+                }
+            }
+            ("CATEGORIES", Some((list, task))) => {
+                for category in split_list(value) {
+                    match list {
+                        None => *list = Some(category),
+                        Some(_) => task.tags.push(category),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Joins folded lines back together.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => result.push('\n'),
+                Some(c) => result.push(c),
+                None => result.push('\\'),
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Splits a comma-separated value, keeping escaped commas.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            ',' if !escaped => items.push(String::new()),
+            _ => items.last_mut().unwrap().push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    items.iter().map(|item| unescape(item.trim())).filter(|item| !item.is_empty()).collect()
+}
+
+/// `20261019T082649Z` (UTC), `20261019T082649` (local) or `20261019`.
+fn parse_time(value: &str) -> Result<DateTime<Local>, String> {
+    let invalid = || format!("Invalid date: {}", value);
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return Ok(Utc.from_utc_datetime(&time).with_timezone(&Local));
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return time.and_local_timezone(Local).earliest().ok_or_else(invalid);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y%m%d").ok().and_then(start_of_day).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_folded_lines_and_escapes() {
+        let text = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:call mom\\, dad\r\n  and \\;kids\\;\r\nCATEGORIES:family,phone\\,calls,\r\n urgent\r\nSTATUS:COMPLETED\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let tasks = parse(text, "default").unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        let (list, task) = &tasks[0];
+        assert_eq!(list, "family");
+        assert_eq!(task.name, "call mom, dad and ;kids;");
+        assert_eq!(task.tags, ["phone,calls", "urgent"]);
+        assert!(task.checked);
+    }
+
+    #[test]
+    fn ignores_nested_components() {
+        let text = "BEGIN:VTODO\nSUMMARY:pay rent\nBEGIN:VALARM\nACTION:DISPLAY\nSUMMARY:reminder\nTRIGGER:-PT15M\nEND:VALARM\nPRIORITY:1\nEND:VTODO\n";
+        let tasks = parse(text, "default").unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].0, "default");
+        assert_eq!(tasks[0].1.name, "pay rent");
+        assert_eq!(tasks[0].1.priority, Some('A'));
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold(&line);
+
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(unfold(&folded), [line]);
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut task = Task::new("buy milk, eggs");
+        task.tags = vec!["shop".to_string()];
+        task.due = NaiveDate::from_ymd_opt(2026, 10, 20);
+        task.priority = Some('B');
+        let tasks = vec![("home".to_string(), task)];
+
+        let text = write(&tasks);
+        assert!(text.contains(&format!("UID:{}@todo-app", task_uuid("home", &tasks[0].1))));
+
+        let parsed = parse(&text, "default").unwrap();
+        assert_eq!(parsed[0].0, "home");
+        assert_eq!(parsed[0].1.name, "buy milk, eggs");
+        assert_eq!(parsed[0].1.tags, ["shop"]);
+        assert_eq!(parsed[0].1.due, tasks[0].1.due);
+        assert_eq!(parsed[0].1.priority, Some('B'));
+    }
+}
@@ -0,0 +1,181 @@
+//! GitHub-flavored Markdown checklists, one `## list` section per list:
+//!
+//! ```markdown
+//! ## work
+//!
+//! - [ ] (A) Write report #q3 (due 2026-10-20)
+//!   - [x] Collect numbers
+//! ```
+//!
+//! Items are written like `tasks` prints them. A nested item is a subtask
+//! of the item above it (see [`Task::parent`]), so nesting survives a round
+//! trip. Bullets without a checkbox are imported as open tasks, other lines
+//! are skipped.
+
+use chrono::NaiveDate;
+
+use crate::files::{parse_priority, Task};
+
+use super::nested;
+
+pub fn parse(text: &str, default_list: &str) -> Result<Vec<(String, Task)>, String> {
+    let mut list = default_list.to_string();
+    let mut indents: Vec<usize> = Vec::new();
+    // Names of the items the current one is nested in, one per level.
+    let mut parents: Vec<String> = Vec::new();
+    let mut tasks = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        if let Some(heading) = heading(line) {
+            list = heading.to_string();
+            indents.clear();
+            parents.clear();
+            continue;
+        }
+
+        let Some((indent, checked, item)) = bullet(line) else {
+            continue;
+        };
+
+        // Nesting depth, whatever the file uses for indentation.
+        while indents.last().is_some_and(|&last| last > indent) {
+            indents.pop();
+        }
+        if indents.last() != Some(&indent) {
+            indents.push(indent);
+        }
+        let depth = indents.len() - 1;
+
+        let mut task = parse_item(item).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        parents.truncate(depth);
+        task.parent = parents.last().cloned();
+        task.checked = checked;
+        parents.push(task.name.clone());
+        tasks.push((list.clone(), task));
+    }
+
+    Ok(tasks)
+}
+
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    match (level, text.strip_prefix(' ')) {
+        (1..=6, Some(text)) if !text.trim().is_empty() => Some(text.trim()),
+        _ => None,
+    }
+}
+
+/// Indentation, checked state and text of a `-`, `*` or `+` bullet.
+fn bullet(line: &str) -> Option<(usize, bool, &str)> {
+    let text = line.trim_start();
+    let indent = line[..line.len() - text.len()].chars().map(|c| if c == '\t' { 4 } else { 1 }).sum();
+
+    let text = text.strip_prefix(['-', '*', '+'])?.strip_prefix(' ')?.trim();
+    let (checked, text) = if let Some(text) = text.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = text.strip_prefix("[x]").or_else(|| text.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        (false, text)
+    };
+
+    Some((indent, checked, text.trim()))
+}
+
+/// Reads back what [`Task`]'s `Display` writes after the checkbox.
+fn parse_item(item: &str) -> Result<Task, String> {
+    let mut item = item;
+    let mut task = Task::new("");
+    task.created = None;
+
+    if let Some((priority, rest)) = item.strip_prefix('(').and_then(|rest| rest.split_once(") ")) {
+        if let Ok(priority) = parse_priority(priority) {
+            task.priority = Some(priority);
+            item = rest;
+        }
+    }
+
+    if let Some((rest, due)) = item.strip_suffix(')').and_then(|rest| rest.rsplit_once(" (due ")) {
+        // Anything but a date is part of the name.
+        if let Ok(due) = NaiveDate::parse_from_str(due, "%Y-%m-%d") {
+            task.due = Some(due);
+            item = rest;
+        }
+    }
+
+    let mut words = item.split(' ').collect::<Vec<_>>();
+    while let Some(tag) = words.last().and_then(|word| word.strip_prefix('#')).filter(|tag| !tag.is_empty()) {
+        task.tags.insert(0, tag.to_string());
+        words.pop();
+    }
+
+    task.name = words.join(" ").trim().to_string();
+    if task.name.is_empty() {
+        return Err("Task without a name".to_string());
+    }
+
+    Ok(task)
+}
+
+pub fn write(tasks: &[(String, Task)]) -> String {
+    let mut text = String::new();
+    let mut current = None;
+
+    for (depth, (list, task)) in nested(tasks) {
+        if current != Some(list) {
+            if current.is_some() {
+                text.push('\n');
+            }
+            text += &format!("## {}\n\n", list);
+            current = Some(list);
+        }
+
+        let item = task.to_string();
+        let item = item.split_once(' ').map_or(item.as_str(), |(_, rest)| rest);
+
+        text += &format!("{}- [{}] {}\n", "  ".repeat(depth), if task.checked { "x" } else { " " }, item);
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_nesting_from_any_indentation() {
+        let text = "- [ ] a\n\t- [x] b\n\t\t* c\n\t- d\n- e\n";
+        let tasks = parse(text, "default").unwrap();
+
+        let nesting = tasks.iter().map(|(_, task)| (task.name.as_str(), task.parent.as_deref())).collect::<Vec<_>>();
+        assert_eq!(nesting, [("a", None), ("b", Some("a")), ("c", Some("b")), ("d", Some("a")), ("e", None)]);
+        assert!(tasks[1].1.checked);
+        assert!(!tasks[2].1.checked);
+    }
+
+    #[test]
+    fn reads_items_like_tasks_prints_them() {
+        let tasks = parse("## work\n\n- [ ] (A) pay (rent) #home #bills (due 2026-10-20)\n- [ ] #1 fan\n", "default").unwrap();
+
+        assert_eq!(tasks[0].0, "work");
+        assert_eq!(tasks[0].1.name, "pay (rent)");
+        assert_eq!(tasks[0].1.priority, Some('A'));
+        assert_eq!(tasks[0].1.tags, ["home", "bills"]);
+        assert_eq!(tasks[0].1.due, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(tasks[1].1.name, "#1 fan");
+        assert_eq!(parse("- [ ] call back (due soon) #phone", "default").unwrap()[0].1.name, "call back (due soon)");
+        assert_eq!(parse("- [ ] call back (due soon)", "default").unwrap()[0].1.name, "call back (due soon)");
+        assert_eq!(parse("- [ ] #tag", "default"), Err("Line 1: Task without a name".to_string()));
+    }
+
+    #[test]
+    fn writes_subtasks_under_their_parent() {
+        let mut child = Task::new("oat");
+        child.parent = Some("milk".to_string());
+        let tasks = vec![("groceries".to_string(), child), ("groceries".to_string(), Task::new("milk"))];
+
+        assert_eq!(write(&tasks), "## groceries\n\n- [ ] milk\n  - [ ] oat\n");
+    }
+}
@@ -0,0 +1,171 @@
+//! Conversion between the task model and the file formats of other tools,
+//! used by `import` and `export`.
+
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+use uuid::Uuid;
+
+use crate::files::Task;
+
+pub mod csv;
+pub mod ical;
+pub mod markdown;
+pub mod org;
+pub mod taskwarrior;
+pub mod todotxt;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum FileFormat {
+    /// todo.txt, lists become `+project`, tags `@context`
+    Todotxt,
+    /// Markdown checklists, one `## list` section per list
+    Markdown,
+    /// CSV with a header row, see `--columns`
+    Csv,
+    /// iCalendar VTODO components, for calendar clients
+    Ical,
+    /// Taskwarrior JSON, as from `task export`
+    Taskwarrior,
+    /// Org-mode, one top-level heading per list
+    Org,
+}
+
+impl FileFormat {
+    /// Guesses the format from the file extension.
+    pub fn from_extension(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "txt" => Some(FileFormat::Todotxt),
+            "md" | "markdown" => Some(FileFormat::Markdown),
+            "csv" => Some(FileFormat::Csv),
+            "ics" | "ical" => Some(FileFormat::Ical),
+            "json" => Some(FileFormat::Taskwarrior),
+            "org" => Some(FileFormat::Org),
+            _ => None,
+        }
+    }
+}
+
+/// Settings only some formats use.
+#[derive(Debug, Default)]
+pub struct FormatOptions {
+    /// CSV column mapping, see [`csv`].
+    pub columns: Option<String>,
+}
+
+/// Parses `text` into `(list, task)` pairs. Tasks that don't name a list go
+/// to `default_list`.
+pub fn import(
+    format: FileFormat,
+    text: &str,
+    default_list: &str,
+    options: &FormatOptions,
+) -> Result<Vec<(String, Task)>, String> {
+    match format {
+        FileFormat::Todotxt => todotxt::parse(text, default_list),
+        FileFormat::Markdown => markdown::parse(text, default_list),
+        FileFormat::Csv => csv::parse(text, default_list, options.columns.as_deref()),
+        FileFormat::Ical => ical::parse(text, default_list),
+        FileFormat::Taskwarrior => taskwarrior::parse(text, default_list),
+        FileFormat::Org => org::parse(text, default_list),
+    }
+}
+
+pub fn export(format: FileFormat, tasks: &[(String, Task)], options: &FormatOptions) -> Result<String, String> {
+    match format {
+        FileFormat::Todotxt => Ok(todotxt::write(tasks)),
+        FileFormat::Markdown => Ok(markdown::write(tasks)),
+        FileFormat::Csv => csv::write(tasks, options.columns.as_deref()),
+        FileFormat::Ical => Ok(ical::write(tasks)),
+        FileFormat::Taskwarrior => taskwarrior::write(tasks),
+        FileFormat::Org => Ok(org::write(tasks)),
+    }
+}
+
+/// The tasks with their nesting depth, each subtask right after its parent
+/// and the subtasks before it. Tasks whose parent is not among the tasks of
+/// their list are at the top level, tasks in a cycle of parents come last.
+fn nested(tasks: &[(String, Task)]) -> Vec<(usize, &(String, Task))> {
+    let parents = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, (list, task))| {
+            let name = task.parent.as_ref()?;
+            (0..tasks.len()).find(|&j| j != i && tasks[j].0 == *list && tasks[j].1.name == *name)
+        })
+        .collect::<Vec<_>>();
+
+    let mut visited = vec![false; tasks.len()];
+    let mut result = Vec::with_capacity(tasks.len());
+    // Roots first, then whatever is left in a cycle of parents.
+    let roots = (0..tasks.len()).filter(|&i| parents[i].is_none()).chain(0..tasks.len()).collect::<Vec<_>>();
+    for root in roots {
+        let mut stack = vec![(root, 0)];
+        while let Some((i, depth)) = stack.pop() {
+            if visited[i] {
+                continue;
+            }
+            visited[i] = true;
+            result.push((depth, &tasks[i]));
+            let children = (0..tasks.len()).filter(|&child| parents[child] == Some(i) && !visited[child]);
+            stack.extend(children.rev().map(|child| (child, depth + 1)).collect::<Vec<_>>());
+        }
+    }
+
+    result
+}
+
+/// Midnight of `date` in local time, for formats that only keep dates.
+fn start_of_day(date: NaiveDate) -> Option<DateTime<Local>> {
+    date.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest()
+}
+
+/// Namespace of the ids from [`task_uuid`].
+const TASK_NAMESPACE: Uuid = Uuid::from_u128(0xd238033e_ac2f_424e_b69d_3dfff98b200b);
+
+/// Id of a task in formats that keep one. It only depends on the list and
+/// the name, so it stays the same across exports, releases and platforms.
+fn task_uuid(list: &str, task: &Task) -> Uuid {
+    Uuid::new_v5(&TASK_NAMESPACE, format!("{}\0{}", list, task.name).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(list: &str, name: &str, parent: Option<&str>) -> (String, Task) {
+        let mut task = Task::new(name);
+        task.parent = parent.map(|parent| parent.to_string());
+        (list.to_string(), task)
+    }
+
+    fn names(tasks: &[(String, Task)]) -> Vec<(usize, &str)> {
+        nested(tasks).into_iter().map(|(depth, (_, task))| (depth, task.name.as_str())).collect()
+    }
+
+    #[test]
+    fn nested_puts_subtasks_under_their_parent() {
+        let tasks = [
+            task("work", "b1", Some("b")),
+            task("work", "a", None),
+            task("work", "b", Some("a")),
+            task("work", "c", None),
+            task("work", "a1", Some("a")),
+        ];
+        assert_eq!(names(&tasks), [(0, "a"), (1, "b"), (2, "b1"), (1, "a1"), (0, "c")]);
+    }
+
+    #[test]
+    fn nested_keeps_missing_and_cyclic_parents_at_the_top() {
+        let tasks = [
+            task("work", "a", Some("gone")),
+            task("home", "b", Some("a")),
+            task("work", "c", Some("d")),
+            task("work", "d", Some("c")),
+            task("work", "e", Some("e")),
+        ];
+        assert_eq!(names(&tasks), [(0, "a"), (0, "b"), (0, "e"), (0, "c"), (1, "d")]);
+    }
+}
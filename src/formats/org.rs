@@ -0,0 +1,271 @@
+//! Org-mode outlines, one top-level heading per list:
+//!
+//! ```org
+//! * work
+//! ** TODO [#A] Write report :q3:
+//! DEADLINE: <2026-10-20 Tue>
+//! :PROPERTIES:
+//! :CREATED:  [2026-10-01 Thu 10:15]
+//! :END:
+//! *** DONE Collect numbers
+//! CLOSED: [2026-10-19 Mon 09:30]
+//! ```
+//!
+//! Deeper headings are subtasks of the heading above them (see
+//! [`Task::parent`]). Subheadings without a `TODO` or `DONE` keyword are
+//! imported as open tasks, body text is skipped. Like in Org-mode, a last
+//! word such as `:x:` after whitespace is read as tags, even if it was the
+//! end of the name of a task without tags.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+
+use crate::files::{parse_priority, Task};
+
+use super::{nested, start_of_day};
+
+pub fn parse(text: &str, default_list: &str) -> Result<Vec<(String, Task)>, String> {
+    let mut list = default_list.to_string();
+    let mut tasks: Vec<(String, Task)> = Vec::new();
+    // Names of the headings the current one is nested in, one per level.
+    let mut parents: Vec<String> = Vec::new();
+    // Whether the lines below the last heading belong to a task.
+    let mut in_task = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let error = |e: String| format!("Line {}: {}", i + 1, e);
+
+        if let Some((level, text)) = heading(line) {
+            in_task = level > 1;
+            parents.truncate(level.saturating_sub(2));
+            if level == 1 {
+                list = text.to_string();
+            } else {
+                let mut task = parse_heading(text).map_err(error)?;
+                task.parent = parents.last().cloned();
+                parents.push(task.name.clone());
+                tasks.push((list.clone(), task));
+            }
+            continue;
+        }
+
+        let Some((_, task)) = tasks.last_mut().filter(|_| in_task) else {
+            continue;
+        };
+        let line = line.trim();
+        if let Some(created) = line.strip_prefix(":CREATED:") {
+            task.created = Some(parse_timestamp(created.trim()).map_err(error)?);
+        } else if ["CLOSED:", "DEADLINE:", "SCHEDULED:"].iter().any(|keyword| line.starts_with(keyword)) {
+            parse_planning(line, task).map_err(error)?;
+        }
+    }
+
+    Ok(tasks)
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let text = line.trim_start_matches('*');
+    let level = line.len() - text.len();
+    match text.strip_prefix(' ') {
+        Some(text) if level > 0 && !text.trim().is_empty() => Some((level, text.trim())),
+        _ => None,
+    }
+}
+
+fn parse_heading(text: &str) -> Result<Task, String> {
+    let mut words = text.split(' ').filter(|word| !word.is_empty()).collect::<Vec<_>>();
+    let mut task = Task::new("");
+    task.created = None;
+
+    match words.first() {
+        Some(&"TODO") => {
+            words.remove(0);
+        }
+        Some(&"DONE") => {
+            words.remove(0);
+            task.checked = true;
+        }
+        _ => {}
+    }
+
+    if let Some(priority) = words.first().and_then(|word| word.strip_prefix("[#")?.strip_suffix(']')) {
+        task.priority = Some(parse_priority(priority)?);
+        words.remove(0);
+    }
+
+    let text = words.join(" ");
+    let (name, tags) = split_tags(&text);
+    task.name = name.to_string();
+    task.tags = tags;
+    if task.name.is_empty() {
+        return Err("Task without a name".to_string());
+    }
+
+    Ok(task)
+}
+
+/// Splits `name :tag1:tag2:` into the name and the tags. Tags need
+/// whitespace before them and may only contain letters, digits, `_`, `@`,
+/// `#` and `%`, as in Org-mode.
+fn split_tags(text: &str) -> (&str, Vec<String>) {
+    let tag = |tag: &str| !tag.is_empty() && tag.chars().all(|c| c.is_alphanumeric() || "_@#%".contains(c));
+    let tags = text
+        .rsplit_once(char::is_whitespace)
+        .filter(|(_, last)| last.len() > 1 && last.starts_with(':') && last.ends_with(':'))
+        .filter(|(_, last)| last[1..last.len() - 1].split(':').all(tag));
+
+    match tags {
+        Some((name, last)) => (name.trim_end(), last[1..last.len() - 1].split(':').map(|tag| tag.to_string()).collect()),
+        None => (text, Vec::new()),
+    }
+}
+
+/// `CLOSED: [...] DEADLINE: <...>`, in any order. `SCHEDULED` is ignored.
+fn parse_planning(line: &str, task: &mut Task) -> Result<(), String> {
+    let mut rest = line;
+    while let Some((keyword, after)) = rest.split_once(':') {
+        let after = after.trim_start();
+        let close = match after.chars().next() {
+            Some('<') => '>',
+            Some('[') => ']',
+            _ => return Err(format!("Invalid planning line: {}", line)),
+        };
+        let end = after.find(close).ok_or_else(|| format!("Invalid planning line: {}", line))? + 1;
+        let timestamp = &after[..end];
+
+        match keyword.trim() {
+            "DEADLINE" => task.due = Some(parse_timestamp(timestamp)?.date_naive()),
+            "CLOSED" => task.completed = Some(parse_timestamp(timestamp)?),
+            _ => {}
+        }
+        rest = &after[end..];
+    }
+    Ok(())
+}
+
+/// `<2026-10-20 Tue>` or `[2026-10-19 Mon 09:30]`.
+fn parse_timestamp(text: &str) -> Result<DateTime<Local>, String> {
+    let invalid = || format!("Invalid date: {}", text);
+
+    let inner = text.get(1..text.len().saturating_sub(1)).ok_or_else(invalid)?;
+    let mut parts = inner.split_whitespace();
+    let date = parts.next().and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()).ok_or_else(invalid)?;
+    let time = parts.find_map(|part| NaiveTime::parse_from_str(part, "%H:%M").ok());
+
+    match time {
+        Some(time) => date.and_time(time).and_local_timezone(Local).earliest().ok_or_else(invalid),
+        None => start_of_day(date).ok_or_else(invalid),
+    }
+}
+
+pub fn write(tasks: &[(String, Task)]) -> String {
+    let mut text = String::new();
+    let mut current = None;
+
+    for (depth, (list, task)) in nested(tasks) {
+        if current != Some(list) {
+            text += &format!("* {}\n", list);
+            current = Some(list);
+        }
+
+        let mut heading = format!("{} {}", "*".repeat(depth + 2), if task.checked { "DONE" } else { "TODO" });
+        if let Some(priority) = task.priority {
+            heading += &format!(" [#{}]", priority);
+        }
+        heading += &format!(" {}", task.name);
+        if !task.tags.is_empty() {
+            heading += &format!(" :{}:", task.tags.join(":"));
+        }
+        text += &heading;
+        text.push('\n');
+
+        let mut planning = Vec::new();
+        if let Some(completed) = task.completed.filter(|_| task.checked) {
+            planning.push(format!("CLOSED: {}", completed.format("[%Y-%m-%d %a %H:%M]")));
+        }
+        if let Some(due) = task.due {
+            planning.push(format!("DEADLINE: {}", due.format("<%Y-%m-%d %a>")));
+        }
+        if !planning.is_empty() {
+            text += &format!("{}\n", planning.join(" "));
+        }
+
+        if let Some(created) = task.created {
+            text += &format!(":PROPERTIES:\n:CREATED:  {}\n:END:\n", created.format("[%Y-%m-%d %a %H:%M]"));
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_planning_lines_in_any_order() {
+        let text = "* work\n** DONE Pay rent\nDEADLINE: <2026-10-20 Tue> CLOSED: [2026-10-19 Mon 09:30]\n** TODO Call\nSCHEDULED: <2026-10-21 Wed>\n";
+        let tasks = parse(text, "default").unwrap();
+
+        let rent = &tasks[0].1;
+        assert_eq!(rent.due, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(rent.completed.map(|c| c.format("%Y-%m-%d %H:%M").to_string()).as_deref(), Some("2026-10-19 09:30"));
+        assert_eq!(tasks[1].1.due, None);
+        assert_eq!(parse("** TODO x\nDEADLINE: 2026-10-20", "default"), Err("Line 2: Invalid planning line: DEADLINE: 2026-10-20".to_string()));
+    }
+
+    #[test]
+    fn reads_nesting_and_skips_body_text() {
+        let text = "* work\n** TODO [#B] a :x:y:\nsome notes\n**** b\n*** DONE c\n** d\n* home\n** e\n";
+        let tasks = parse(text, "default").unwrap();
+
+        let nesting = tasks.iter().map(|(list, task)| (list.as_str(), task.name.as_str(), task.parent.as_deref())).collect::<Vec<_>>();
+        assert_eq!(nesting, [
+            ("work", "a", None),
+            ("work", "b", Some("a")),
+            ("work", "c", Some("a")),
+            ("work", "d", None),
+            ("home", "e", None),
+        ]);
+        assert_eq!(tasks[0].1.priority, Some('B'));
+        assert_eq!(tasks[0].1.tags, ["x", "y"]);
+        assert!(tasks[2].1.checked);
+    }
+
+    #[test]
+    fn writes_subtasks_under_their_parent() {
+        let mut child = Task::new("Collect numbers");
+        child.parent = Some("Write report".to_string());
+        child.created = None;
+        let mut parent = Task::new("Write report");
+        parent.created = None;
+        let tasks = vec![("work".to_string(), child), ("work".to_string(), parent)];
+
+        assert_eq!(write(&tasks), "* work\n** TODO Write report\n*** TODO Collect numbers\n");
+    }
+
+    #[test]
+    fn tags_need_whitespace_before_them() {
+        assert_eq!(split_tags("ratio 1:x:"), ("ratio 1:x:", Vec::new()));
+        assert_eq!(split_tags("odd :x y:"), ("odd :x y:", Vec::new()));
+        assert_eq!(split_tags("empty ::"), ("empty ::", Vec::new()));
+        assert_eq!(split_tags("a :b::c:"), ("a :b::c:", Vec::new()));
+        assert_eq!(split_tags(":x:"), (":x:", Vec::new()));
+        assert_eq!(split_tags("a\t:x:@home:"), ("a", vec!["x".to_string(), "@home".to_string()]));
+    }
+
+    #[test]
+    fn names_and_tags_survive_a_round_trip() {
+        let mut tasks = Vec::new();
+        for (name, tags) in [("ratio 1:x:", vec![]), ("ratio 1:x:", vec!["t"]), ("at 10:30:", vec!["a", "b_c"]), ("keep :x:", vec!["t"])] {
+            let mut task = Task::new(name);
+            task.created = None;
+            task.tags = tags.into_iter().map(String::from).collect();
+            tasks.push(("work".to_string(), task));
+        }
+
+        let parsed = parse(&write(&tasks), "default").unwrap();
+
+        let names = |tasks: &[(String, Task)]| tasks.iter().map(|(_, task)| (task.name.clone(), task.tags.clone())).collect::<Vec<_>>();
+        assert_eq!(names(&parsed), names(&tasks));
+    }
+}
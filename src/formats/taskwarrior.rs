@@ -0,0 +1,147 @@
+//! Taskwarrior's JSON, as written by `task export` and read by `task import`.
+//! The project is the list, `completed` tasks are checked and deleted ones
+//! are skipped. Priorities `A`, `B` and `C` map to `H`, `M` and `L`, lower
+//! ones to `L`.
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::files::Task;
+
+use super::{start_of_day, task_uuid};
+
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Record {
+    uuid: String,
+    description: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+}
+
+/// Reads a JSON array, or one object per line as older versions export.
+pub fn parse(text: &str, default_list: &str) -> Result<Vec<(String, Task)>, String> {
+    let records: Vec<Record> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text).map_err(|e| e.to_string())?
+    } else {
+        text.lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("Line {}: {}", i + 1, e)))
+            .collect::<Result<_, _>>()?
+    };
+
+    records
+        .into_iter()
+        // Recurring tasks are templates, their instances are exported as well.
+        .filter(|record| record.status != "deleted" && record.status != "recurring")
+        .map(|record| {
+            if record.description.trim().is_empty() {
+                return Err("Task without a name".to_string());
+            }
+
+            let mut task = Task::new(record.description.trim());
+            task.checked = record.status == "completed";
+            task.tags = record.tags;
+            task.priority = match record.priority.as_deref() {
+                Some("H") => Some('A'),
+                Some("M") => Some('B'),
+                Some("L") => Some('C'),
+                _ => None,
+            };
+            task.created = record.entry.as_deref().map(parse_time).transpose()?;
+            task.completed = record.end.as_deref().map(parse_time).transpose()?.filter(|_| task.checked);
+            task.due = record.due.as_deref().map(parse_time).transpose()?.map(|due| due.date_naive());
+
+            let list = record.project.filter(|project| !project.is_empty());
+            Ok((list.unwrap_or(default_list.to_string()), task))
+        })
+        .collect()
+}
+
+pub fn write(tasks: &[(String, Task)]) -> Result<String, String> {
+    let records = tasks
+        .iter()
+        .map(|(list, task)| Record {
+            uuid: task_uuid(list, task).to_string(),
+            description: task.name.clone(),
+            status: if task.checked { "completed" } else { "pending" }.to_string(),
+            project: Some(list.clone()),
+            tags: task.tags.clone(),
+            priority: task.priority.map(|priority| match priority {
+                'A' => "H",
+                'B' => "M",
+                _ => "L",
+            }.to_string()),
+            entry: task.created.map(|created| format_time(&created)),
+            end: task.completed.filter(|_| task.checked).map(|completed| format_time(&completed)),
+            due: task.due.and_then(start_of_day).map(|due| format_time(&due)),
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&records).map(|json| json + "\n").map_err(|e| e.to_string())
+}
+
+fn format_time(time: &DateTime<Local>) -> String {
+    time.with_timezone(&Utc).format(TIME_FORMAT).to_string()
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Local>, String> {
+    let time = NaiveDateTime::parse_from_str(value, TIME_FORMAT).map_err(|_| format!("Invalid date: {}", value))?;
+    Ok(Utc.from_utc_datetime(&time).with_timezone(&Local))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_arrays_and_lines() {
+        let array = r#"[{"uuid":"x","description":"pay rent","status":"completed","project":"home","priority":"M","end":"20261019T080000Z"},
+            {"uuid":"y","description":"old","status":"deleted"}]"#;
+        let lines = "{\"description\":\"call mom\",\"status\":\"pending\",\"tags\":[\"phone\"]},\n{\"description\":\"template\",\"status\":\"recurring\"}\n";
+
+        let tasks = parse(array, "default").unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].0, "home");
+        assert!(tasks[0].1.checked);
+        assert_eq!(tasks[0].1.priority, Some('B'));
+        assert!(tasks[0].1.completed.is_some());
+
+        let tasks = parse(lines, "default").unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].0, "default");
+        assert_eq!(tasks[0].1.tags, ["phone"]);
+    }
+
+    #[test]
+    fn rejects_tasks_without_a_name() {
+        assert_eq!(parse(r#"[{"description":" ","status":"pending"}]"#, "default"), Err("Task without a name".to_string()));
+    }
+
+    #[test]
+    fn writes_stable_rfc_4122_ids() {
+        let tasks = vec![("home".to_string(), Task::new("pay rent"))];
+        let records: Vec<Record> = serde_json::from_str(&write(&tasks).unwrap()).unwrap();
+        let uuid = uuid::Uuid::parse_str(&records[0].uuid).unwrap();
+
+        assert_eq!(uuid.get_version_num(), 5);
+        assert_eq!(uuid.get_variant(), uuid::Variant::RFC4122);
+        assert_eq!(uuid, task_uuid("home", &Task::new("pay rent")));
+        assert_ne!(uuid, task_uuid("work", &Task::new("pay rent")));
+    }
+}
@@ -0,0 +1,255 @@
+//! [todo.txt](https://github.com/todotxt/todo.txt): one task per line,
+//!
+//! ```text
+//! (A) 2026-10-01 Call mom +family @phone due:2026-10-20
+//! x 2026-10-19 2026-10-01 Pay rent +home pri:B
+//! ```
+//!
+//! The first `+project` is the list. Projects can't contain whitespace, so
+//! whitespace and `%` in list names and tags are percent-encoded
+//! (`+my%20list`) and decoded again on import. Names are encoded the same
+//! way where they would be read as something else: `%`, whitespace other
+//! than single spaces, and words like `+word`, `@word`, `due:`, `pri:` or a
+//! leading `x`, `(A)` or date (`%2Bword`, `due%3Asoon`, `%78`). Completed
+//! tasks keep their priority in `pri:`, as the spec suggests.
+
+use chrono::NaiveDate;
+
+use crate::files::{parse_priority, Task};
+
+use super::start_of_day;
+
+pub fn parse(text: &str, default_list: &str) -> Result<Vec<(String, Task)>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_line(line, default_list).map_err(|e| format!("Line {}: {}", i + 1, e)))
+        .collect()
+}
+
+fn parse_line(line: &str, default_list: &str) -> Result<(String, Task), String> {
+    let mut words = line.split_whitespace().peekable();
+    let mut task = Task::new("");
+    task.created = None;
+
+    let date = |word: Option<&&str>| word.and_then(|word| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok());
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        task.checked = true;
+        if let Some(completed) = date(words.peek()) {
+            words.next();
+            task.completed = start_of_day(completed);
+        }
+    }
+
+    if let Some(priority) = words.peek().and_then(|word| word.strip_prefix('(')?.strip_suffix(')')) {
+        task.priority = Some(parse_priority(priority)?);
+        words.next();
+    }
+
+    if let Some(created) = date(words.peek()) {
+        words.next();
+        task.created = start_of_day(created);
+    }
+
+    let mut list = None;
+    let mut name = Vec::new();
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty() && list.is_none()) {
+            list = Some(decode(project));
+        } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            task.tags.push(decode(context));
+        } else if let Some(due) = word.strip_prefix("due:") {
+            task.due = Some(NaiveDate::parse_from_str(due, "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", due))?);
+        } else if let Some(priority) = word.strip_prefix("pri:") {
+            task.priority = Some(parse_priority(priority)?);
+        } else {
+            name.push(word);
+        }
+    }
+
+    if name.is_empty() {
+        return Err("Task without a name".to_string());
+    }
+    task.name = decode(&name.join(" "));
+
+    Ok((list.unwrap_or(default_list.to_string()), task))
+}
+
+pub fn write(tasks: &[(String, Task)]) -> String {
+    tasks.iter().map(|(list, task)| write_line(list, task) + "\n").collect()
+}
+
+fn write_line(list: &str, task: &Task) -> String {
+    let mut words = Vec::new();
+
+    if task.checked {
+        words.push("x".to_string());
+        // The spec only allows a creation date after a completion date.
+        if let Some(completed) = task.completed {
+            words.push(completed.format("%Y-%m-%d").to_string());
+            words.extend(task.created.map(|created| created.format("%Y-%m-%d").to_string()));
+        }
+    } else {
+        words.extend(task.priority.map(|priority| format!("({})", priority)));
+        words.extend(task.created.map(|created| created.format("%Y-%m-%d").to_string()));
+    }
+
+    words.push(encode_name(&task.name));
+    if !list.is_empty() {
+        words.push(format!("+{}", encode_word(list)));
+    }
+    words.extend(task.tags.iter().map(|tag| format!("@{}", encode_word(tag))));
+    words.extend(task.due.map(|due| format!("due:{}", due)));
+    if task.checked {
+        words.extend(task.priority.map(|priority| format!("pri:{}", priority)));
+    }
+
+    words.join(" ")
+}
+
+/// Keys of the `key:value` words [`parse_line`] reads.
+const KEYS: [&str; 2] = ["due", "pri"];
+
+fn escape(c: char, encoded: &mut String) {
+    let mut bytes = [0; 4];
+    c.encode_utf8(&mut bytes).bytes().for_each(|byte| *encoded += &format!("%{:02X}", byte));
+}
+
+/// Encodes a list name or tag as a single word.
+fn encode_word(word: &str) -> String {
+    let mut encoded = String::new();
+    for c in word.chars() {
+        if c.is_whitespace() || c == '%' {
+            escape(c, &mut encoded);
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// Encodes a task name so [`parse_line`] reads it back unchanged.
+fn encode_name(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut encoded = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let single_space = c == ' '
+            && i > 0
+            && !chars[i - 1].is_whitespace()
+            && chars.get(i + 1).is_some_and(|next| !next.is_whitespace());
+        if c == '%' || (c.is_whitespace() && !single_space) {
+            escape(c, &mut encoded);
+        } else {
+            encoded.push(c);
+        }
+    }
+
+    let words = encoded.split(' ').enumerate().map(|(i, word)| {
+        let special = (word.len() > 1 && (word.starts_with('+') || word.starts_with('@')))
+            || (i == 0 && (word == "x" || (word.starts_with('(') && word.ends_with(')'))))
+            || (i == 0 && NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok());
+        if let Some((key, value)) = word.split_once(':').filter(|(key, _)| KEYS.contains(key)) {
+            format!("{}%3A{}", key, value)
+        } else if special {
+            let mut chars = word.chars();
+            let mut encoded = String::new();
+            escape(chars.next().unwrap(), &mut encoded);
+            encoded + chars.as_str()
+        } else {
+            word.to_string()
+        }
+    });
+    words.collect::<Vec<_>>().join(" ")
+}
+
+/// Reverses [`encode_word`] and [`encode_name`]. A `%` not followed by two
+/// hex digits is kept.
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = text.get(i + 1..i + 3).filter(|_| bytes[i] == b'%').and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_names_survive_a_round_trip() {
+        for list in ["my list", "a_b", "50% off", "tab\there", "wide\u{3000}space", "%zz"] {
+            let line = write_line(list, &Task::new("task"));
+            assert_eq!(line.split_whitespace().count(), 3, "{}", line);
+            assert_eq!(parse_line(&line, "default").unwrap().0, list);
+        }
+        assert_eq!(encode_word("my list"), "my%20list");
+        assert_eq!(decode("50%off"), "50%off");
+    }
+
+    #[test]
+    fn tasks_survive_a_round_trip() {
+        let mut tasks = Vec::new();
+        for name in ["email @bob about +release", "x marks the spot", "(A) is a grade", "2026-10-01 was a day", "due:foo and pri:Z", "a  b\tc ", " 50% off", "ratio 1:x"] {
+            tasks.push(("default".to_string(), Task::new(name)));
+        }
+        let mut task = Task::new("pay rent");
+        task.checked = true;
+        task.due = NaiveDate::from_ymd_opt(2026, 10, 20);
+        task.priority = Some('B');
+        task.tags = vec!["bank".to_string(), "on hold".to_string()];
+        tasks.push(("my list".to_string(), task));
+        let mut task = Task::new("call mom");
+        task.priority = Some('A');
+        tasks.push(("family".to_string(), task));
+
+        let text = write(&tasks);
+        assert!(text.lines().next().unwrap().ends_with(" email %40bob about %2Brelease +default"), "{}", text);
+        let parsed = parse(&text, "other").unwrap();
+
+        assert_eq!(parsed.len(), tasks.len());
+        for ((list, task), (parsed_list, parsed)) in tasks.iter().zip(&parsed) {
+            assert_eq!(parsed_list, list);
+            assert_eq!(parsed.name, task.name);
+            assert_eq!(parsed.checked, task.checked, "{}", task.name);
+            assert_eq!(parsed.due, task.due);
+            assert_eq!(parsed.priority, task.priority);
+            assert_eq!(parsed.tags, task.tags);
+        }
+    }
+
+    #[test]
+    fn reads_completion_priority_and_dates() {
+        let (list, task) = parse_line("x 2026-10-19 2026-10-01 pay rent +home @bank due:2026-10-20 pri:B", "default").unwrap();
+
+        assert_eq!(list, "home");
+        assert_eq!(task.name, "pay rent");
+        assert!(task.checked);
+        assert_eq!(task.completed.map(|c| c.date_naive()), NaiveDate::from_ymd_opt(2026, 10, 19));
+        assert_eq!(task.created.map(|c| c.date_naive()), NaiveDate::from_ymd_opt(2026, 10, 1));
+        assert_eq!(task.tags, ["bank"]);
+        assert_eq!(task.due, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(task.priority, Some('B'));
+    }
+
+    #[test]
+    fn reports_broken_lines() {
+        assert_eq!(parse("ok\n+work @home\n", "default"), Err("Line 2: Task without a name".to_string()));
+        assert_eq!(parse("a due:soon", "default"), Err("Line 1: Invalid date: soon".to_string()));
+        assert!(parse("(AB) a", "default").is_err());
+    }
+}
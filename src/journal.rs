@@ -0,0 +1,313 @@
+//! Undo/redo journal. Every save is compared with what is on disk and the
+//! difference is recorded as one [`Operation`], so `undo` can apply the
+//! inverse changes and `redo` the original ones.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::files::{ArchivedTask, Task, TrashedItem, LIST_NOT_FOUND, TASK_NOT_FOUND};
+
+/// Number of operations kept for undo.
+pub const JOURNAL_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    AddList { list: String, tasks: Vec<Task> },
+    RemoveList { list: String, tasks: Vec<Task> },
+    AddTask { list: String, index: usize, task: Task },
+    RemoveTask { list: String, index: usize, task: Task },
+    UpdateTask { list: String, before: Task, after: Task },
+    /// `removed` archive entries at `index` were replaced with `added`.
+    Archive { index: usize, removed: Vec<ArchivedTask>, added: Vec<ArchivedTask> },
+    /// Same as `Archive` for the trash.
+    Trash { index: usize, removed: Vec<TrashedItem>, added: Vec<TrashedItem> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub time: DateTime<Local>,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub done: Vec<Operation>,
+    pub undone: Vec<Operation>,
+}
+
+/// One line of the append-only history log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub time: DateTime<Local>,
+    pub action: String,
+    pub list: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+}
+
+impl Journal {
+    /// Adds a new operation, dropping the redo history.
+    pub fn record(&mut self, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+
+        self.done.push(Operation { time: Local::now(), changes });
+        if self.done.len() > JOURNAL_LIMIT {
+            self.done.drain(..self.done.len() - JOURNAL_LIMIT);
+        }
+        self.undone.clear();
+    }
+}
+
+/// The files an operation can change.
+pub struct Files {
+    pub tasks: HashMap<String, Vec<Task>>,
+    pub archive: Vec<ArchivedTask>,
+    pub trash: Vec<TrashedItem>,
+}
+
+impl Operation {
+    pub fn undo(&self, files: &mut Files) -> Result<(), String> {
+        self.changes.iter().rev().try_for_each(|change| change.inverse().apply(files))
+    }
+
+    pub fn redo(&self, files: &mut Files) -> Result<(), String> {
+        self.changes.iter().try_for_each(|change| change.apply(files))
+    }
+
+    pub fn touches_archive(&self) -> bool {
+        self.changes.iter().any(|change| matches!(change, Change::Archive { .. }))
+    }
+
+    pub fn touches_trash(&self) -> bool {
+        self.changes.iter().any(|change| matches!(change, Change::Trash { .. }))
+    }
+}
+
+impl Change {
+    pub fn inverse(&self) -> Change {
+        match self.clone() {
+            Change::AddList { list, tasks } => Change::RemoveList { list, tasks },
+            Change::RemoveList { list, tasks } => Change::AddList { list, tasks },
+            Change::AddTask { list, index, task } => Change::RemoveTask { list, index, task },
+            Change::RemoveTask { list, index, task } => Change::AddTask { list, index, task },
+            Change::UpdateTask { list, before, after } => Change::UpdateTask { list, before: after, after: before },
+            Change::Archive { index, removed, added } => Change::Archive { index, removed: added, added: removed },
+            Change::Trash { index, removed, added } => Change::Trash { index, removed: added, added: removed },
+        }
+    }
+
+    fn apply(&self, files: &mut Files) -> Result<(), String> {
+        let data = &mut files.tasks;
+        match self {
+            Change::AddList { list, tasks } => {
+                if data.contains_key(list) {
+                    return Err(format!("List already exists: {}", list));
+                }
+                data.insert(list.clone(), tasks.clone());
+            }
+            Change::RemoveList { list, .. } => {
+                data.remove(list).ok_or_else(|| format!("{}: {}", LIST_NOT_FOUND, list))?;
+            }
+            Change::AddTask { list, index, task } => {
+                let tasks = data.get_mut(list).ok_or_else(|| format!("{}: {}", LIST_NOT_FOUND, list))?;
+                if tasks.contains(task) {
+                    return Err(format!("Task already exists: {}", task.name));
+                }
+                tasks.insert((*index).min(tasks.len()), task.clone());
+            }
+            Change::RemoveTask { list, task, .. } => {
+                let tasks = data.get_mut(list).ok_or_else(|| format!("{}: {}", LIST_NOT_FOUND, list))?;
+                let idx = tasks.iter().position(|t| t == task).ok_or_else(|| format!("{}: {}", TASK_NOT_FOUND, task.name))?;
+                tasks.remove(idx);
+            }
+            Change::UpdateTask { list, before, after } => {
+                let tasks = data.get_mut(list).ok_or_else(|| format!("{}: {}", LIST_NOT_FOUND, list))?;
+                let task = tasks.iter_mut().find(|t| *t == before).ok_or_else(|| format!("{}: {}", TASK_NOT_FOUND, before.name))?;
+                *task = after.clone();
+            }
+            Change::Archive { index, removed, added } => splice(&mut files.archive, *index, removed, added, "archive")?,
+            Change::Trash { index, removed, added } => splice(&mut files.trash, *index, removed, added, "trash")?,
+        }
+        Ok(())
+    }
+}
+
+impl Change {
+    /// History entries for this change, one per task it touched.
+    pub fn history(&self, time: DateTime<Local>) -> Vec<HistoryEntry> {
+        let entry = |action: &str, list: &str, task: Option<&str>| HistoryEntry {
+            time,
+            action: action.to_string(),
+            list: list.to_string(),
+            task: task.map(|task| task.to_string()),
+        };
+
+        match self {
+            Change::AddList { list, .. } => vec![entry("add-list", list, None)],
+            Change::RemoveList { list, .. } => vec![entry("remove-list", list, None)],
+            Change::AddTask { list, task, .. } => vec![entry("add", list, Some(&task.name))],
+            Change::RemoveTask { list, task, .. } => vec![entry("remove", list, Some(&task.name))],
+            Change::UpdateTask { list, before, after } => {
+                let action = match (before.checked, after.checked) {
+                    (false, true) => "check",
+                    (true, false) => "uncheck",
+                    _ => "edit",
+                };
+                vec![entry(action, list, Some(&after.name))]
+            }
+            Change::Archive { removed, added, .. } => removed
+                .iter()
+                .map(|archived| entry("unarchive", &archived.list, Some(&archived.task.name)))
+                .chain(added.iter().map(|archived| entry("archive", &archived.list, Some(&archived.task.name))))
+                .collect(),
+            // Trashing goes with a `remove` or `remove-list` that is logged already.
+            Change::Trash { .. } => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::AddList { list, .. } => write!(f, "add list {}", list),
+            Change::RemoveList { list, tasks } => write!(f, "remove list {} ({})", list, count(tasks.len(), "task")),
+            Change::AddTask { list, task, .. } => write!(f, "add \"{}\" to {}", task.name, list),
+            Change::RemoveTask { list, task, .. } => write!(f, "remove \"{}\" from {}", task.name, list),
+            Change::UpdateTask { list, before, after } => match (before.checked, after.checked) {
+                (false, true) => write!(f, "check \"{}\" in {}", after.name, list),
+                (true, false) => write!(f, "uncheck \"{}\" in {}", after.name, list),
+                _ => write!(f, "edit \"{}\" in {}", after.name, list),
+            },
+            Change::Archive { removed, added, .. } => match (removed.len(), added.len()) {
+                (0, added) => write!(f, "archive {}", count(added, "task")),
+                (removed, 0) => write!(f, "take {} out of the archive", count(removed, "task")),
+                _ => write!(f, "edit the archive"),
+            },
+            Change::Trash { removed, added, .. } => match (removed.len(), added.len()) {
+                (0, added) => write!(f, "move {} to the trash", count(added, "item")),
+                (removed, 0) => write!(f, "take {} out of the trash", count(removed, "item")),
+                _ => write!(f, "edit the trash"),
+            },
+        }
+    }
+}
+
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 { format!("1 {}", noun) } else { format!("{} {}s", n, noun) }
+}
+
+/// Replaces `removed` at `index` with `added`, if those are still the
+/// entries there.
+fn splice<T: Serialize + Clone>(entries: &mut Vec<T>, index: usize, removed: &[T], added: &[T], name: &str) -> Result<(), String> {
+    let unchanged = entries
+        .get(index..index + removed.len())
+        .is_some_and(|current| current.iter().zip(removed).all(|(a, b)| identical(a, b)));
+    if !unchanged {
+        return Err(format!("The {} has changed", name));
+    }
+    entries.splice(index..index + removed.len(), added.iter().cloned());
+    Ok(())
+}
+
+/// Changes turning `before` into `after`. Tasks are matched by name, which
+/// is unique within a list. Removed tasks come in descending and added ones
+/// in ascending order of their index, so the changes can be applied (and
+/// their inverses in reverse) one after another.
+pub fn diff(before: &HashMap<String, Vec<Task>>, after: &HashMap<String, Vec<Task>>) -> Vec<Change> {
+    let mut lists = before.keys().chain(after.keys()).collect::<Vec<_>>();
+    lists.sort();
+    lists.dedup();
+
+    let mut changes = Vec::new();
+
+    for list in lists {
+        let (old, new) = match (before.get(list), after.get(list)) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(old), None) => {
+                changes.push(Change::RemoveList { list: list.clone(), tasks: old.clone() });
+                continue;
+            }
+            (None, Some(new)) => {
+                changes.push(Change::AddList { list: list.clone(), tasks: new.clone() });
+                continue;
+            }
+            (None, None) => continue,
+        };
+
+        for (index, task) in old.iter().enumerate().rev() {
+            if !new.contains(task) {
+                changes.push(Change::RemoveTask { list: list.clone(), index, task: task.clone() });
+            }
+        }
+        for (index, task) in new.iter().enumerate() {
+            match old.iter().find(|t| *t == task) {
+                None => changes.push(Change::AddTask { list: list.clone(), index, task: task.clone() }),
+                Some(old_task) if !identical(old_task, task) => changes.push(Change::UpdateTask {
+                    list: list.clone(),
+                    before: old_task.clone(),
+                    after: task.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+    }
+
+    changes
+}
+
+pub fn diff_archive(before: &[ArchivedTask], after: &[ArchivedTask]) -> Option<Change> {
+    diff_entries(before, after).map(|(index, removed, added)| Change::Archive { index, removed, added })
+}
+
+pub fn diff_trash(before: &[TrashedItem], after: &[TrashedItem]) -> Option<Change> {
+    diff_entries(before, after).map(|(index, removed, added)| Change::Trash { index, removed, added })
+}
+
+/// The archive and the trash only grow at the end or lose a few entries, so
+/// the part between the common prefix and suffix is all that changed.
+fn diff_entries<T: Serialize + Clone>(before: &[T], after: &[T]) -> Option<(usize, Vec<T>, Vec<T>)> {
+    let same = |a: &T, b: &T| identical(a, b);
+
+    let prefix = before.iter().zip(after).take_while(|(a, b)| same(a, b)).count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+
+    let removed = &before[prefix..before.len() - suffix];
+    let added = &after[prefix..after.len() - suffix];
+
+    if removed.is_empty() && added.is_empty() {
+        None
+    } else {
+        Some((prefix, removed.to_vec(), added.to_vec()))
+    }
+}
+
+/// `Task` compares by name only, this compares every field.
+fn identical<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splice_checks_the_removed_entries() {
+        let mut entries = vec![1, 2, 3];
+        splice(&mut entries, 1, &[2], &[4, 5], "trash").unwrap();
+        assert_eq!(entries, [1, 4, 5, 3]);
+
+        assert_eq!(splice(&mut entries, 1, &[2], &[], "trash"), Err("The trash has changed".to_string()));
+        assert_eq!(splice(&mut entries, 3, &[3, 6], &[], "trash"), Err("The trash has changed".to_string()));
+        assert_eq!(entries, [1, 4, 5, 3]);
+    }
+}
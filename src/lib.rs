@@ -1,4 +1,11 @@
+pub mod backup;
 pub mod cli;
 pub mod files;
 pub mod ui;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod filter;
+pub mod formats;
+pub mod journal;
+pub mod scan;
+pub mod search;
+pub mod sort;
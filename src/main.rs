@@ -4,15 +4,20 @@ use todo_app::files::check_dir;
 use todo_app::ui::app;
 
 fn main() {
-    check_dir();
+    let args = match cli::get_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    check_dir(args.options.global);
 
     let config = get_config().unwrap();
 
-    match cli::get_args() {
-        Ok(args) => match args.subcommand {
-            Some(subcmd) => parse_args(subcmd, config),
-            None => app(),
-        },
-        Err(e) => eprintln!("{}", e),
+    match args.subcommand {
+        Some(subcmd) => parse_args(subcmd, args.options, config),
+        None => app(),
     }
 }
\ No newline at end of file
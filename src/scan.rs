@@ -0,0 +1,182 @@
+//! `TODO`, `FIXME` and `XXX` comments in source code, for `scan`.
+//!
+//! A comment becomes a task named `text (path:line)` and tagged with the
+//! keyword. Tasks are matched back to comments by name without the line
+//! number, so a comment that moves keeps its task. Tasks without one of the
+//! keyword tags are never touched by a scan.
+
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::files::Task;
+
+const KEYWORDS: [&str; 3] = ["TODO", "FIXME", "XXX"];
+
+#[derive(Debug, Clone)]
+pub struct Comment {
+    /// Relative to the scanned directory, with `/` separators.
+    pub path: String,
+    pub line: usize,
+    /// `TODO`, `FIXME` or `XXX`.
+    pub keyword: String,
+    pub text: String,
+}
+
+impl Comment {
+    pub fn task_name(&self) -> String {
+        let text = if self.text.is_empty() { &self.keyword } else { &self.text };
+        format!("{} ({}:{})", text, self.path, self.line)
+    }
+}
+
+/// The part of a scanned task's name that stays when the comment moves, or
+/// `None` for tasks that didn't come from a scan. Those are tagged with
+/// their keyword, so a task someone named `... (notes:12)` is left alone.
+pub fn task_key(task: &Task) -> Option<&str> {
+    let tagged = task.tags.iter().any(|tag| KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(tag)));
+    if tagged { name_key(&task.name) } else { None }
+}
+
+/// The name of a scanned task without the line number.
+pub fn name_key(name: &str) -> Option<&str> {
+    let (key, line) = name.strip_suffix(')')?.rsplit_once(':')?;
+    (!line.is_empty() && line.chars().all(|c| c.is_ascii_digit()) && key.contains(" (")).then_some(key)
+}
+
+/// Name for the list of a scanned directory.
+pub fn project_name(root: &Path) -> String {
+    root.canonicalize()
+        .ok()
+        .and_then(|root| Some(root.file_name()?.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "project".to_string())
+}
+
+/// Comments in the files under `root`, skipping hidden and ignored files.
+/// Files that aren't UTF-8 text are skipped as well.
+pub fn scan(root: &Path) -> Result<Vec<Comment>, String> {
+    if !root.exists() {
+        return Err(format!("No such file or directory: {}", root.display()));
+    }
+
+    let matcher = Matcher::new()?;
+
+    let mut comments = Vec::new();
+    for entry in ignore::WalkBuilder::new(root).require_git(false).sort_by_file_path(Ord::cmp).build() {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+
+        let path = match entry.path().strip_prefix(root) {
+            Ok(path) if !path.as_os_str().is_empty() => path,
+            _ => Path::new(entry.file_name()),
+        };
+        let path = path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+
+        for (i, line) in content.lines().enumerate() {
+            if let Some((keyword, text)) = matcher.comment(line) {
+                comments.push(Comment {
+                    path: path.clone(),
+                    line: i + 1,
+                    keyword: keyword.to_string(),
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(comments)
+}
+
+/// Finds the keyword comment in a line of code.
+struct Matcher {
+    /// Start of a comment: `*`, `--` and `;` only at the start of a line,
+    /// the others there or after whitespace.
+    marker: Regex,
+    keyword: Regex,
+}
+
+impl Matcher {
+    fn new() -> Result<Self, String> {
+        Ok(Self {
+            marker: Regex::new(r"^\s*(?:\*|--|;+)|(?:^|\s)(?://+!?|/\*+!?|#+|<!--)").map_err(|e| e.to_string())?,
+            keyword: Regex::new(&format!(r"^\s*({})\b(?:\([^)]*\))?:?\s*(.*)", KEYWORDS.join("|"))).map_err(|e| e.to_string())?,
+        })
+    }
+
+    /// The keyword and the text of the comment. Markers inside a string,
+    /// after an odd number of `"`, don't start one.
+    fn comment<'a>(&self, line: &'a str) -> Option<(&'a str, &'a str)> {
+        self.marker
+            .find_iter(line)
+            .filter(|marker| !line[..marker.start()].chars().filter(|c| *c == '"').fold(false, |inside, _| !inside))
+            .find_map(|marker| {
+                let captures = self.keyword.captures(&line[marker.end()..])?;
+                let text = captures.get(2)?.as_str().trim().trim_end_matches("*/").trim_end_matches("-->").trim();
+                Some((captures.get(1)?.as_str(), text))
+            })
+    }
+}
+
+/// What syncing a scan changed in its list.
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    pub added: usize,
+    /// Comments that moved to another line.
+    pub moved: usize,
+    /// Comments that are gone.
+    pub checked: usize,
+    /// Comments that came back after their task was checked.
+    pub reopened: usize,
+}
+
+impl SyncSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added + self.moved + self.checked + self.reopened == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_comments() {
+        let matcher = Matcher::new().unwrap();
+
+        assert_eq!(matcher.comment("    // TODO: handle errors"), Some(("TODO", "handle errors")));
+        assert_eq!(matcher.comment("x = 1  # FIXME(bob) wrong value"), Some(("FIXME", "wrong value")));
+        assert_eq!(matcher.comment("/* XXX */"), Some(("XXX", "")));
+        assert_eq!(matcher.comment(" * TODO in a block"), Some(("TODO", "in a block")));
+        assert_eq!(matcher.comment("-- TODO index"), Some(("TODO", "index")));
+        assert_eq!(matcher.comment("<!-- TODO: alt text -->"), Some(("TODO", "alt text")));
+    }
+
+    #[test]
+    fn skips_markers_in_strings_and_words() {
+        let matcher = Matcher::new().unwrap();
+
+        assert_eq!(matcher.comment(r#"let url = "http:// TODO";"#), None);
+        assert_eq!(matcher.comment(r##"print("# TODO not a comment")"##), None);
+        assert_eq!(matcher.comment(r##"print("# TODO") # TODO real one"##), Some(("TODO", "real one")));
+        assert_eq!(matcher.comment("a * TODO"), None);
+        assert_eq!(matcher.comment("issue#TODO"), None);
+        assert_eq!(matcher.comment("// TODOS are fine"), None);
+    }
+
+    #[test]
+    fn keys_only_tagged_tasks() {
+        let mut task = Task::new("handle errors (src/main.rs:3)");
+        assert_eq!(task_key(&task), None);
+
+        task.tags.push("todo".to_string());
+        assert_eq!(task_key(&task), Some("handle errors (src/main.rs"));
+
+        task.name = "handle errors".to_string();
+        assert_eq!(task_key(&task), None);
+    }
+}
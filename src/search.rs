@@ -0,0 +1,119 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchMode {
+    Substring,
+    Regex,
+}
+
+#[derive(Debug)]
+pub struct Matcher {
+    regex: Regex,
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, mode: SearchMode, ignore_case: bool) -> Result<Self, String> {
+        let pattern = match mode {
+            SearchMode::Substring => regex::escape(pattern),
+            SearchMode::Regex => pattern.to_string(),
+        };
+        let regex = RegexBuilder::new(&pattern).case_insensitive(ignore_case).build();
+
+        match regex {
+            Ok(regex) => Ok(Self { regex }),
+            Err(e) => Err(format!("Invalid pattern: {}", e)),
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Byte ranges of every non-empty match in `text`.
+    pub fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect()
+    }
+}
+
+/// Scores how well `pattern` fuzzy-matches `text`.
+///
+/// Every character of the pattern has to appear in the text in the same
+/// order (ignoring case). Consecutive characters and characters at the start
+/// of a word are rewarded, skipped characters are penalized. Returns `None`
+/// if the pattern is not a subsequence of the text.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text = text.chars().flat_map(char::to_lowercase).collect::<Vec<_>>();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev: Option<usize> = None;
+
+    for c in pattern.chars().flat_map(char::to_lowercase) {
+        let idx = pos + text[pos..].iter().position(|t| *t == c)?;
+
+        score += 1;
+        if idx == 0 {
+            score += 10;
+        } else if matches!(text[idx - 1], ' ' | '_' | '-' | '/' | '.' | ':') {
+            score += 5;
+        }
+        match prev {
+            Some(prev) if prev + 1 == idx => score += 8,
+            Some(prev) => score -= (idx - prev - 1).min(5) as i64,
+            None => score -= idx.min(5) as i64,
+        }
+
+        prev = Some(idx);
+        pos = idx + 1;
+    }
+
+    Some(score)
+}
+
+/// Candidates matching `pattern`, best first.
+pub fn fuzzy_rank<'a>(pattern: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut scored = candidates
+        .filter_map(|c| fuzzy_score(pattern, c).map(|score| (score, c)))
+        .collect::<Vec<_>>();
+
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    scored.into_iter().map(|(_, c)| c.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_needs_every_character_in_order() {
+        assert!(fuzzy_score("mlk", "milk").is_some());
+        assert!(fuzzy_score("MLK", "milk").is_some());
+        assert!(fuzzy_score("klm", "milk").is_none());
+        assert!(fuzzy_score("milkk", "milk").is_none());
+        assert_eq!(fuzzy_score("", "milk"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_score_rewards_consecutive_and_word_starts() {
+        let consecutive = fuzzy_score("mil", "milk").unwrap();
+        let scattered = fuzzy_score("mil", "make it last").unwrap();
+        assert!(consecutive > scattered);
+
+        let word_start = fuzzy_score("b", "call bob").unwrap();
+        let inside = fuzzy_score("b", "cabal").unwrap();
+        assert!(word_start > inside);
+    }
+
+    #[test]
+    fn fuzzy_rank_sorts_best_first_and_drops_misses() {
+        let candidates = ["great peace", "buy milk", "report bug"];
+        assert_eq!(fuzzy_rank("rep", candidates.into_iter()), vec!["report bug", "great peace"]);
+        assert!(fuzzy_rank("xyz", candidates.into_iter()).is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
+
+use crate::files::Task;
+
+/// Group label with the `(list, task)` pairs in it.
+pub type Group = (String, Vec<(String, Task)>);
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SortKey {
+    Name,
+    Status,
+    Created,
+    Due,
+    Priority,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    List,
+    Status,
+    Tag,
+    DueBucket,
+}
+
+/// Stable sort, so tasks with equal keys keep their list order.
+/// Tasks without a due date, priority or creation time go last.
+pub fn sort_tasks(tasks: &mut [(String, Task)], key: SortKey) {
+    match key {
+        SortKey::Name => tasks.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name)),
+        SortKey::Status => tasks.sort_by_key(|(_, t)| t.checked),
+        SortKey::Created => tasks.sort_by_key(|(_, t)| (t.created.is_none(), t.created)),
+        SortKey::Due => tasks.sort_by_key(|(_, t)| (t.due.is_none(), t.due)),
+        SortKey::Priority => tasks.sort_by_key(|(_, t)| (t.priority.is_none(), t.priority)),
+    }
+}
+
+/// Splits tasks into labeled groups, keeping the order of tasks inside each
+/// group. A task with several tags shows up in every tag group.
+pub fn group_tasks(tasks: Vec<(String, Task)>, group_by: GroupBy) -> Vec<Group> {
+    let mut groups: Vec<(usize, Group)> = Vec::new();
+    let today = Local::now().date_naive();
+
+    for (list, task) in tasks {
+        for (order, label) in group_labels(&list, &task, group_by, today) {
+            match groups.iter_mut().find(|(_, (l, _))| *l == label) {
+                Some((_, (_, group))) => group.push((list.clone(), task.clone())),
+                None => groups.push((order, (label, vec![(list.clone(), task.clone())]))),
+            }
+        }
+    }
+
+    groups.sort_by(|(a, (a_label, _)), (b, (b_label, _))| (a, a_label).cmp(&(b, b_label)));
+
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+fn group_labels(list: &str, task: &Task, group_by: GroupBy, today: NaiveDate) -> Vec<(usize, String)> {
+    match group_by {
+        GroupBy::List => vec![(0, list.to_string())],
+        GroupBy::Status => vec![if task.checked {
+            (1, "done".to_string())
+        } else {
+            (0, "open".to_string())
+        }],
+        GroupBy::Tag if task.tags.is_empty() => vec![(1, "untagged".to_string())],
+        GroupBy::Tag => task.tags.iter().map(|tag| (0, format!("#{}", tag))).collect(),
+        GroupBy::DueBucket => vec![due_bucket(task, today)],
+    }
+}
+
+/// Bucket of the due date relative to `today`. "next 7 days" is the rest
+/// of the week from today, not the calendar week.
+fn due_bucket(task: &Task, today: NaiveDate) -> (usize, String) {
+    let due = match task.due {
+        Some(due) => due,
+        None => return (5, "no due date".to_string()),
+    };
+
+    let days = (due - today).num_days();
+
+    match days {
+        ..=-1 => (0, "overdue".to_string()),
+        0 => (1, "today".to_string()),
+        1 => (2, "tomorrow".to_string()),
+        2..=7 => (3, "next 7 days".to_string()),
+        _ => (4, "later".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, due: Option<&str>, priority: Option<char>) -> (String, Task) {
+        let mut task = Task::new(name);
+        task.due = due.map(|due| due.parse().unwrap());
+        task.priority = priority;
+        ("work".to_string(), task)
+    }
+
+    fn names(tasks: &[(String, Task)]) -> Vec<&str> {
+        tasks.iter().map(|(_, task)| task.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sorting_keeps_ties_in_order_and_missing_values_last() {
+        let mut tasks = vec![
+            task("a", None, None),
+            task("b", Some("2026-10-20"), Some('B')),
+            task("c", Some("2026-10-19"), None),
+            task("d", Some("2026-10-20"), Some('A')),
+            task("e", None, Some('B')),
+        ];
+
+        sort_tasks(&mut tasks, SortKey::Due);
+        assert_eq!(names(&tasks), ["c", "b", "d", "a", "e"]);
+
+        sort_tasks(&mut tasks, SortKey::Priority);
+        assert_eq!(names(&tasks), ["d", "b", "e", "c", "a"]);
+
+        tasks[1].1.checked = true;
+        sort_tasks(&mut tasks, SortKey::Status);
+        assert_eq!(names(&tasks), ["d", "e", "c", "a", "b"]);
+    }
+
+    #[test]
+    fn due_buckets_have_fixed_boundaries() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let bucket = |due: Option<&str>| due_bucket(&task("a", due, None).1, today).1;
+
+        assert_eq!(bucket(Some("2026-10-18")), "overdue");
+        assert_eq!(bucket(Some("2026-10-19")), "today");
+        assert_eq!(bucket(Some("2026-10-20")), "tomorrow");
+        assert_eq!(bucket(Some("2026-10-21")), "next 7 days");
+        assert_eq!(bucket(Some("2026-10-26")), "next 7 days");
+        assert_eq!(bucket(Some("2026-10-27")), "later");
+        assert_eq!(bucket(None), "no due date");
+    }
+
+    #[test]
+    fn groups_are_ordered_and_tags_repeat_tasks() {
+        let mut tagged = task("a", None, None);
+        tagged.1.tags = vec!["y".to_string(), "x".to_string()];
+        let mut done = task("b", None, None);
+        done.1.checked = true;
+        let tasks = vec![tagged, done, task("c", None, None)];
+
+        let labels = |group_by| {
+            group_tasks(tasks.clone(), group_by)
+                .into_iter()
+                .map(|(label, tasks)| format!("{} {}", label, names(&tasks).join(",")))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels(GroupBy::Status), ["open a,c", "done b"]);
+        assert_eq!(labels(GroupBy::Tag), ["#x a", "#y a", "untagged b,c"]);
+        assert_eq!(labels(GroupBy::DueBucket), ["no due date a,b,c"]);
+    }
+}
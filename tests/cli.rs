@@ -1,55 +1,812 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 
-//Works only with test-threads = 1
-//TODO: Add functionality to create separate files for each test
+/// A store in a fresh temporary home directory, removed again on drop.
+struct TempStore {
+    home: std::path::PathBuf,
+}
 
-fn create_test_list() -> String {
-    let mut cmd = Command::cargo_bin("todo-app").unwrap();
-    let lists = cmd.arg("lists").output().unwrap();
-    let mut lists = std::str::from_utf8(&lists.stdout).unwrap().split("\n").collect::<Vec<_>>();
-    lists.pop();
-    let mut name = uuid::Uuid::new_v4().to_string();
-    while lists.contains(&name.as_str()) {
-        name = uuid::Uuid::new_v4().to_string();
+impl TempStore {
+    fn new() -> Self {
+        let home = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir(&home).unwrap();
+        Self { home }
+    }
+
+    fn path(&self) -> &std::path::Path {
+        &self.home
     }
-    let mut cmd = Command::cargo_bin("todo-app").unwrap();
-    cmd.args(&["add-list", &name]).assert().success();
-    name
-}
 
-fn delete_test_list(list: &str) {
-    let mut cmd = Command::cargo_bin("todo-app").unwrap();
-    cmd.args(&["remove-list", list]).assert().success();
+    /// `todo-app` using this store.
+    fn cmd(&self) -> Command {
+        let mut cmd = Command::cargo_bin("todo-app").unwrap();
+        cmd.env("HOME", &self.home);
+        cmd
+    }
+
+    /// Like [`TempStore::cmd`], run in `dir`.
+    fn cmd_in(&self, dir: &std::path::Path) -> Command {
+        let mut cmd = self.cmd();
+        cmd.current_dir(dir);
+        cmd
+    }
 }
 
-fn add_some_task(list: &str) -> assert_cmd::assert::Assert{
-    let mut cmd = Command::cargo_bin("todo-app").unwrap();
-    cmd.args(&["add", "some_task", list]).assert()
+impl Drop for TempStore {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
 }
 
 #[test]
 fn add_tasks_works() {
-    let list = create_test_list();
-    
-    let assert = add_some_task(list.as_str());
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
 
-    delete_test_list(&list);
+    let assert = store.cmd().args(["add", "some_task", "work"]).assert();
 
     assert.success().stdout("Task added\n");
 }
 
 #[test]
 fn tasks_list_works() {
-    let mut cmd = Command::cargo_bin("todo-app").unwrap();
-    
-    let list = create_test_list();
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
+
+    let assert = store.cmd().arg("tasks").assert();
 
-    add_some_task(list.as_str());
+    assert.success().stdout(predicate::str::contains("some_task"));
+}
 
-    let assert = cmd.arg("tasks").assert();
+#[test]
+fn search_works() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
 
-    delete_test_list(&list);
+    let substring = store.cmd().args(["search", "me_ta"]).assert();
+    let ignore_case = store.cmd().args(["search", "-i", "SOME_TASK"]).assert();
+    let regex = store.cmd().args(["search", "-e", "^some_t.sk$"]).assert();
+    let regex_ignore_case = store.cmd().args(["search", "-e", "-i", "^SOME_T.SK$"]).assert();
 
-    assert.success().stdout(predicate::str::contains("some_task"));
-}
\ No newline at end of file
+    let expected = "[work] x some_task";
+    substring.success().stdout(predicate::str::contains(expected));
+    ignore_case.success().stdout(predicate::str::contains(expected));
+    regex.success().stdout(predicate::str::contains(expected));
+    regex_ignore_case.success().stdout(predicate::str::contains(expected));
+}
+
+#[test]
+fn check_by_prefix_works() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
+
+    let exact = store.cmd().args(["check", "--exact", "some_t", "work"]).assert();
+    let prefix = store.cmd().args(["check", "some_t", "work"]).assert();
+
+    exact.stderr(predicate::str::contains("Task not found"));
+    prefix.success().stdout("Task checked\n");
+}
+
+#[test]
+fn tasks_filter_works() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add-list", "home"]).assert().success();
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
+    store.cmd().args(["add", "tagged_task", "work", "--tag", "backend", "--due", "2000-01-01"]).assert().success();
+    store.cmd().args(["add", "tagged_task", "home", "--tag", "backend", "--due", "2000-01-01"]).assert().success();
+
+    let filtered = store.cmd().args([
+        "tasks",
+        "--where",
+        "list:work and (tag:backend or \"nothing\") and due<today and not status:done",
+    ]).assert();
+    let invalid = store.cmd().args(["tasks", "--where", "(status:open"]).assert();
+
+    filtered.success().stdout("[work]\nx tagged_task #backend (due 2000-01-01)\n");
+    invalid.stderr(predicate::str::contains("Missing ')'"));
+}
+
+#[test]
+fn saved_views_work() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add", "fix login", "-t", "backend"]).assert().success();
+    store.cmd().args(["add", "fix layout", "-t", "frontend"]).assert().success();
+    store.cmd().args(["add", "deploy", "-t", "backend"]).assert().success();
+    store.cmd().args(["check", "deploy"]).assert().success();
+
+    let config = store.path().join(".todo-app/config.toml");
+    let content = std::fs::read_to_string(&config).unwrap();
+    let views = "[views]\nbackend = \"tag:backend and not status:done\"\nbugs = \"view:backend or layout\"\n";
+    std::fs::write(&config, content.replace("[views]\n", views)).unwrap();
+
+    let view = store.cmd().args(["tasks", "--view", "bugs"]).assert();
+    let combined = store.cmd().args(["tasks", "--view", "bugs", "--where", "login"]).assert();
+    let in_query = store.cmd().args(["check", "--where", "view:backend", "--yes"]).assert();
+    let unknown = store.cmd().args(["tasks", "--view", "nope"]).assert();
+
+    view.success().stdout("[default]\nx fix login #backend\nx fix layout #frontend\n");
+    combined.success().stdout("[default]\nx fix login #backend\n");
+    in_query.success();
+    unknown.stderr("Unknown view: nope\n");
+}
+
+#[test]
+fn tasks_sort_and_group_works() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+
+    for (task, priority) in [("b_task", "A"), ("c_task", "C"), ("a_task", "B")] {
+        store.cmd().args(["add", task, "work", "-p", priority]).assert().success();
+    }
+    store.cmd().args(["check", "c_task", "work"]).assert().success();
+
+    let by_name = store.cmd().args(["tasks", "work", "--sort", "name"]).assert();
+    let by_status = store.cmd().args(["tasks", "work", "--sort", "priority", "--group-by", "status"]).assert();
+
+    by_name.success().stdout("x (B) a_task\nx (A) b_task\n☑ (C) c_task\n");
+    by_status.success().stdout("[open]\nx (A) b_task\nx (B) a_task\n[done]\n☑ (C) c_task\n");
+}
+
+#[test]
+fn machine_readable_formats_work() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add", "some, \"quoted\" task", "work", "-t", "a", "-t", "b"]).assert().success();
+
+    let json = store.cmd().args(["tasks", "work", "--format", "json"]).output().unwrap();
+    let csv = store.cmd().args(["--format", "csv", "tasks", "work"]).assert();
+    let lists = store.cmd().args(["lists", "--format", "ndjson"]).assert();
+    let unsupported = store.cmd().args(["add", "task", "work", "--format", "json"]).assert();
+
+    let json: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+    assert_eq!(json[0]["list"], "work");
+    assert_eq!(json[0]["name"], "some, \"quoted\" task");
+    assert_eq!(json[0]["checked"], false);
+    assert_eq!(json[0]["tags"], serde_json::json!(["a", "b"]));
+    assert_eq!(json[0]["due"], serde_json::Value::Null);
+
+    csv.success().stdout(predicate::str::starts_with(
+        "list,name,checked,due,tags,priority,created,completed\nwork,\"some, \"\"quoted\"\" task\",false,,a;b,,",
+    ));
+    lists.success().stdout("{\"name\":\"work\",\"tasks\":1,\"checked\":0}\n");
+    unsupported.failure().stderr("--format is not supported by 'add'\n");
+}
+
+#[test]
+fn tasks_table_works() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
+    store.cmd().args(["add", &"long_task".repeat(20), "work", "--due", "2000-01-01", "-t", "x"]).assert().success();
+
+    let table = store.cmd().args(["tasks", "work", "--table"]).env("COLUMNS", "80").output().unwrap();
+
+    let table = String::from_utf8(table.stdout).unwrap();
+    let lines = table.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("STATUS  LIST  NAME"), "{}", table);
+    assert!(lines[1].starts_with("x       work  some_task "), "{}", table);
+    assert!(lines[2].contains("…  2000-01-01  #x"));
+    assert!(lines.iter().all(|line| line.chars().count() <= 80));
+}
+
+#[test]
+fn picker_without_terminal_does_not_panic() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add", "some_task"]).assert().success();
+
+    store.cmd()
+        .arg("check")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("interactive terminal"));
+}
+
+#[test]
+fn external_picker_works() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add", "some_task"]).assert().success();
+
+    let config = store.path().join(".todo-app/config.toml");
+    let set_picker = |picker: &str| {
+        let content = std::fs::read_to_string(&config).unwrap();
+        let content = content.replace("picker = \"builtin\"", picker);
+        std::fs::write(&config, content).unwrap();
+    };
+
+    set_picker("picker = { command = { program = \"head\", args = [\"-n1\"] } }");
+    let checked = store.cmd().arg("check").assert();
+    let preview = store.cmd().args(["preview", "default: some_task"]).assert();
+
+    let content = std::fs::read_to_string(&config).unwrap();
+    std::fs::write(&config, content.replace("program = \"head\"", "program = \"no-such-picker\"")).unwrap();
+    let missing = store.cmd().arg("check").assert();
+
+    let content = std::fs::read_to_string(&config).unwrap();
+    let content = content.replace("program = \"no-such-picker\", args = [\"-n1\"]", "program = \"sh\", args = [\"-c\", \"exit 130\"]");
+    std::fs::write(&config, &content).unwrap();
+    let aborted = store.cmd().arg("check").assert();
+
+    std::fs::write(&config, content.replace("exit 130", "echo broken >&2; exit 2")).unwrap();
+    let failed = store.cmd().arg("check").assert();
+
+    checked.success().stdout("Task checked\n");
+    preview.success().stdout(predicate::str::contains("Name: some_task\nList: default\nStatus: done\n"));
+    missing.success().stderr(predicate::str::contains("Picker 'no-such-picker' not found"));
+    aborted.success().stdout("").stderr("Nothing selected, 'sh' was aborted\n");
+    failed.success().stdout("").stderr("broken\nPicker 'sh' failed (exit status: 2): broken\n");
+}
+
+#[test]
+fn picked_tasks_are_removed_together() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add", "--stdin"]).write_stdin("first\nsecond\n").assert().success();
+
+    let config = store.path().join(".todo-app/config.toml");
+    let content = std::fs::read_to_string(&config).unwrap();
+    std::fs::write(&config, content.replace("picker = \"builtin\"", "picker = { command = { program = \"cat\" } }")).unwrap();
+
+    let removed = store.cmd().arg("remove").assert();
+    let undone = store.cmd().arg("undo").assert();
+    let tasks = store.cmd().arg("tasks").assert();
+
+    removed.success().stdout("Tasks deleted\n");
+    undone.success();
+    tasks.success().stdout("[default]\nx first\nx second\n");
+}
+
+#[test]
+fn scripted_answers_work() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add-list", "home"]).assert().success();
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
+
+    let declined = store.cmd().args(["check", "--no", "some_task", "home"]).assert();
+    let accepted = store.cmd().args(["check", "some_task", "home", "--yes"]).assert();
+    store.cmd().args(["add", "some_task_other", "work"]).assert().success();
+    let ambiguous = store.cmd().args(["remove", "--non-interactive", "some_t", "work"]).assert();
+    let picker = store.cmd().args(["remove", "--non-interactive"]).assert();
+    let tasks = store.cmd().args(["tasks", "work"]).assert();
+
+    declined.success().stdout("").stderr("Task not found, but it is in: work; rerun with --yes to check it there\n");
+    accepted.success().stdout("Task checked\n").stderr("The task was found in another list: work\n");
+    ambiguous.stderr("Ambiguous task name, candidates: some_task, some_task_other\n");
+    picker.stderr("No task given and prompts are disabled\n");
+    tasks.success().stdout("☑ some_task\nx some_task_other\n");
+}
+
+#[test]
+fn stdin_batch_works() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add-list", "home"]).assert().success();
+
+    let added = store.cmd().args(["add", "--stdin", "work", "-t", "batch"])
+        .write_stdin("first\n\nsecond\nhome: third\nfirst\nTODO: fourth\n")
+        .assert();
+    let checked = store.cmd().args(["check", "--stdin", "work"])
+        .write_stdin("1\nhome:third\nmissing\n")
+        .assert();
+    let removed = store.cmd().args(["remove", "--stdin", "work"])
+        .write_stdin("sec\nTODO: fourth\nthird\n")
+        .assert();
+    let tasks = store.cmd().arg("tasks").assert();
+
+    added.success()
+        .stdout("4 tasks added, 1 failed\n")
+        .stderr("first: Task already exists\n");
+    checked.success()
+        .stdout("2 tasks checked, 0 unchecked, 1 failed\n")
+        .stderr("missing: Task not found\n");
+    removed.success()
+        .stdout("2 tasks deleted, 1 failed\n")
+        .stderr("third: Task not found, but it is in: home\n");
+    tasks.success().stdout("[home]\n☑ third #batch\n[work]\n☑ first #batch\n");
+}
+
+#[test]
+fn bulk_operations_work() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add-list", "home"]).assert().success();
+    store.cmd().args(["add", "--stdin", "work", "-t", "bulk"]).write_stdin("first\nsecond\n").assert().success();
+    store.cmd().args(["add", "third", "work"]).assert().success();
+
+    let filter = "list:work and tag:bulk";
+    let declined = store.cmd().args(["check", "--where", filter, "--non-interactive"]).assert();
+    let checked = store.cmd().args(["check", "--where", filter, "--yes"]).assert();
+    let moved = store.cmd().args(["move", "home", "--where", "list:work and status:done", "-y"]).assert();
+    let removed = store.cmd().args(["remove", "-y", "--where", "list:work"]).assert();
+    let tasks = store.cmd().args(["tasks", "--format", "plain"]).assert();
+
+    let preview = "2 tasks will be checked/unchecked:\n[work] x first #bulk\n[work] x second #bulk\n";
+    declined.success().stdout(preview);
+    checked.success().stdout(format!("{}2 tasks checked, 0 unchecked\n", preview));
+    moved.success().stdout(predicate::str::ends_with("2 tasks moved\n"));
+    removed.success().stdout("1 task will be deleted:\n[work] x third\n1 task deleted\n");
+    tasks.success().stdout("home: first\nhome: second\n");
+}
+
+#[test]
+fn clean_and_archive_work() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add", "--stdin", "work"]).write_stdin("open task\ndone task\n").assert().success();
+    store.cmd().args(["check", "done task", "work"]).assert().success();
+
+    let cleaned = store.cmd().args(["clean", "work"]).assert();
+    let after_clean = store.cmd().args(["tasks", "work"]).assert();
+    let archived = store.cmd().args(["archive", "list", "work"]).assert();
+    let restored = store.cmd().args(["archive", "restore", "done task", "work"]).assert();
+    let after_restore = store.cmd().args(["tasks", "work"]).assert();
+    let emptied = store.cmd().args(["archive", "list", "work"]).assert();
+
+    let today = chrono::Local::now().format("%Y-%m-%d");
+    cleaned.success().stdout("1 task archived\n");
+    after_clean.success().stdout("x open task\n");
+    archived.success().stdout(format!("[work]\n☑ done task (completed {})\n", today));
+    restored.success().stdout("Task restored to work\n");
+    after_restore.success().stdout("x open task\n☑ done task\n");
+    emptied.success().stderr("The archive is empty\n");
+}
+
+#[test]
+fn undo_and_redo_work() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add", "first"]).assert().success();
+    store.cmd().args(["add", "second"]).assert().success();
+    store.cmd().args(["check", "first"]).assert().success();
+    store.cmd().args(["remove-list", "--force", "default"]).assert().success();
+
+    let undone = store.cmd().args(["undo", "2"]).assert();
+    let after_undo = store.cmd().args(["tasks", "default"]).assert();
+    let redone = store.cmd().arg("redo").assert();
+    let after_redo = store.cmd().args(["tasks", "default"]).assert();
+    store.cmd().args(["add", "third"]).assert().success();
+    let nothing = store.cmd().arg("redo").assert();
+
+    undone.success().stdout("Undone: remove list default (2 tasks)\nUndone: check \"first\" in default\n");
+    after_undo.success().stdout("x first\nx second\n");
+    redone.success().stdout("Redone: check \"first\" in default\n");
+    after_redo.success().stdout("☑ first\nx second\n");
+    nothing.success().stderr("Nothing to redo\n");
+}
+
+#[test]
+fn log_works() {
+    let store = TempStore::new();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add-list", "home"]).assert().success();
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
+    store.cmd().args(["check", "some_task", "work"]).assert().success();
+    store.cmd().args(["add", "other_task", "home"]).assert().success();
+    store.cmd().args(["remove", "some_task", "work"]).assert().success();
+
+    let log = store.cmd().args(["log", "--list", "work", "--since", "today"]).output().unwrap();
+    let future = store.cmd().args(["log", "--list", "work", "--since", "tomorrow"]).assert();
+
+    let log = String::from_utf8(log.stdout).unwrap();
+    let actions = log
+        .lines()
+        .map(|line| line.split_whitespace().skip(2).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>();
+    assert_eq!(actions, [
+        "add-list [work]",
+        "add [work] some_task",
+        "check [work] some_task",
+        "remove [work] some_task",
+    ]);
+    future.success().stderr("No changes found\n");
+}
+
+#[test]
+fn trash_works() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add-list", "work"]).assert().success();
+    store.cmd().args(["add", "some_task"]).assert().success();
+    store.cmd().args(["add", "other_task", "work"]).assert().success();
+    store.cmd().args(["remove", "some_task"]).assert().success();
+    store.cmd().args(["remove-list", "-f", "work"]).assert().success();
+
+    let trash = store.cmd().arg("trash").output().unwrap();
+    let restored_task = store.cmd().args(["trash", "restore", "default: some_task"]).assert();
+    let restored_list = store.cmd().args(["trash", "restore", "work"]).assert();
+    let tasks = store.cmd().args(["tasks", "--format", "plain"]).assert();
+    store.cmd().args(["remove", "some_task"]).assert().success();
+    let declined = store.cmd().args(["trash", "empty", "--non-interactive"]).assert();
+    let emptied = store.cmd().args(["trash", "empty", "--yes"]).assert();
+    let empty = store.cmd().arg("trash").assert();
+
+    let trash = String::from_utf8(trash.stdout).unwrap();
+    let items = trash.lines().map(|line| line.split_once(' ').unwrap().1.split_once(' ').unwrap().1).collect::<Vec<_>>();
+    assert_eq!(items, ["[default] x some_task", "[work] (list, 1 task)"]);
+    restored_task.success().stdout("Task restored to default\n");
+    restored_list.success().stdout("List restored\n");
+    tasks.success().stdout("default: some_task\nwork: other_task\n");
+    declined.success().stdout("");
+    emptied.success().stdout("Trash emptied\n");
+    empty.success().stderr("The trash is empty\n");
+}
+
+#[test]
+fn expired_trash_keeps_undo_working() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    for task in ["a", "b", "c"] {
+        store.cmd().args(["add", task]).assert().success();
+    }
+    store.cmd().args(["remove", "a"]).assert().success();
+    store.cmd().args(["remove", "b"]).assert().success();
+
+    let trash_file = store.path().join(".todo-app").join("trash.json");
+    let mut trash: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&trash_file).unwrap()).unwrap();
+    trash[0]["deleted"] = "2000-01-01T00:00:00+00:00".into();
+    std::fs::write(&trash_file, trash.to_string()).unwrap();
+
+    let hidden = store.cmd().arg("trash").assert();
+    let undone = store.cmd().arg("undo").assert();
+    let tasks = store.cmd().args(["tasks", "--format", "plain"]).assert();
+    store.cmd().args(["remove", "c"]).assert().success();
+    let trash = store.cmd().args(["trash"]).output().unwrap();
+
+    hidden.success().stdout(predicate::str::contains(" a").not());
+    undone.success();
+    tasks.success().stdout("default: b\ndefault: c\n");
+    let trash = String::from_utf8(trash.stdout).unwrap();
+    assert_eq!(trash.lines().count(), 1);
+    assert!(trash.ends_with("[default] x c\n"));
+}
+
+#[test]
+fn remove_list_asks_first() {
+    let store = TempStore::new();
+    for list in ["default", "work", "empty"] {
+        store.cmd().args(["add-list", list]).assert().success();
+    }
+    store.cmd().args(["add", "some_task", "work"]).assert().success();
+
+    let declined = store.cmd().args(["remove-list", "work"]).write_stdin("n\n").assert();
+    let kept = store.cmd().args(["tasks", "work"]).assert();
+    let accepted = store.cmd().args(["remove-list", "work", "--yes"]).assert();
+    let removed_empty = store.cmd().args(["remove-list", "empty", "--non-interactive"]).assert();
+    let default = store.cmd().args(["remove-list", "default", "--yes"]).assert();
+
+    declined.success().stdout("work has 1 open and 0 completed tasks\n").stderr("Remove it anyway? [y/N]\n");
+    kept.success().stdout("x some_task\n");
+    accepted.success().stdout("work has 1 open and 0 completed tasks\nList removed\n");
+    removed_empty.success().stdout("List removed\n");
+    default.success().stderr("Refusing to remove the default list without --force\n");
+}
+
+#[test]
+fn backups_work() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+
+    let config = store.path().join(".todo-app/config.toml");
+    let content = std::fs::read_to_string(&config).unwrap();
+    let content = content.replace("last = 10", "last = 2").replace("daily = 7", "daily = 0").replace("weekly = 4", "weekly = 0");
+    std::fs::write(&config, content).unwrap();
+
+    // Older backups, as if taken after adding the first and second task.
+    let backups = store.path().join(".todo-app/backups");
+    std::fs::remove_dir_all(&backups).unwrap();
+    std::fs::create_dir(&backups).unwrap();
+    let task = |name: &str| format!("{{\"name\":\"{}\",\"checked\":false}}", name);
+    std::fs::write(backups.join("tasks-20000101-000000.json"), format!("{{\"default\":[{}]}}", task("first"))).unwrap();
+    std::fs::write(backups.join("tasks-20000102-000000.json"), format!("{{\"default\":[{},{}]}}", task("first"), task("second"))).unwrap();
+    std::fs::write(store.path().join(".todo-app/tasks.json"), format!("{{\"default\":[{},{}]}}", task("first"), task("second"))).unwrap();
+    store.cmd().args(["add", "third"]).assert().success();
+
+    let list = store.cmd().args(["backup", "list"]).output().unwrap();
+    let list = String::from_utf8(list.stdout).unwrap();
+    let restored = store.cmd().args(["backup", "restore", "20000102-000000"]).assert();
+    let tasks = store.cmd().args(["tasks", "default"]).assert();
+
+    assert_eq!(list.lines().count(), 2);
+    assert!(list.lines().next().unwrap().ends_with(" 1 list, 3 tasks"));
+    assert_eq!(list.lines().nth(1).unwrap(), "20000102-000000 1 list, 2 tasks");
+    restored.success().stdout("Backup restored\n");
+    tasks.success().stdout("x first\nx second\n");
+}
+
+#[test]
+fn todotxt_round_trip_works() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add-list", "home"]).assert().success();
+    store.cmd().args(["add", "call mom", "-p", "A", "-t", "phone", "--due", "2026-10-20"]).assert().success();
+    store.cmd().args(["add", "pay rent", "home", "-p", "B"]).assert().success();
+    store.cmd().args(["add", "water plants", "home", "-t", "garden", "-t", "weekly"]).assert().success();
+    store.cmd().args(["check", "pay rent", "home"]).assert().success();
+
+    let before = store.cmd().arg("tasks").output().unwrap();
+    let file = store.path().join("todo.txt");
+    let exported = store.cmd().args(["export", "todotxt", "-o", file.to_str().unwrap()]).assert();
+    let text = std::fs::read_to_string(&file).unwrap();
+
+    store.cmd().args(["remove-list", "-f", "default"]).assert().success();
+    store.cmd().args(["remove-list", "-f", "home"]).assert().success();
+    let imported = store.cmd().args(["import", "todotxt", file.to_str().unwrap()]).assert();
+    let after = store.cmd().arg("tasks").output().unwrap();
+
+    let parsed = store.cmd()
+        .args(["import", "todotxt", "--list", "inbox"])
+        .write_stdin("x 2026-10-02 2026-10-01 done thing +work @office pri:C\n(B) 2026-10-01 plain thing due:2026-11-01\n")
+        .assert();
+    let inbox = store.cmd().args(["tasks", "--format", "plain", "--where", "list:inbox or list:work"]).assert();
+
+    let today = chrono::Local::now().format("%Y-%m-%d");
+    exported.success().stdout("3 tasks exported\n");
+    assert_eq!(text, format!(
+        "(A) {0} call mom +default @phone due:2026-10-20\n\
+         x {0} {0} pay rent +home pri:B\n\
+         {0} water plants +home @garden @weekly\n",
+        today,
+    ));
+    imported.success().stdout("3 tasks imported\n");
+    assert_eq!(String::from_utf8(after.stdout).unwrap(), String::from_utf8(before.stdout).unwrap());
+    parsed.success().stdout("2 tasks imported\n");
+    inbox.success().stdout("inbox: plain thing\nwork: done thing\n");
+}
+
+#[test]
+fn markdown_round_trip_works() {
+    let store = TempStore::new();
+
+    let markdown = "\
+# Notes
+
+Some text that is not a task.
+
+## groceries
+
+- [ ] milk
+    - [x] oat #vegan
+        * soy
+- bread
+
+## work
+
+- [x] (B) pay rent #home (due 2026-10-20)
+";
+
+    let imported = store.cmd().args(["import", "markdown"]).write_stdin(markdown).assert();
+    let tasks = store.cmd().args(["tasks", "--format", "plain"]).assert();
+    let exported = store.cmd().args(["export", "markdown"]).assert();
+
+    imported.success().stdout("5 tasks imported\n");
+    tasks.success().stdout("groceries: milk\ngroceries: oat\ngroceries: soy\ngroceries: bread\nwork: pay rent\n");
+    exported.success().stdout("\
+## groceries
+
+- [ ] milk
+  - [x] oat #vegan
+    - [ ] soy
+- [ ] bread
+
+## work
+
+- [x] (B) pay rent #home (due 2026-10-20)
+");
+}
+
+#[test]
+fn csv_import_and_export_work() {
+    let store = TempStore::new();
+
+    let file = store.path().join("sheet.csv");
+    std::fs::write(
+        &file,
+        "Task,Done,Project,Notes\n\"multi\nline, \"\"quoted\"\" task\",yes,sheet,ignored\nplain,,,\n",
+    )
+    .unwrap();
+
+    let mapping = "name=Task,checked=Done,list=Project";
+    let imported = store.cmd().args(["import", file.to_str().unwrap(), "--columns", mapping]).assert();
+    let exported = store.cmd().args(["export", "--format", "csv", "--columns", mapping]).assert();
+    let unmapped = store.cmd().args(["import", file.to_str().unwrap()]).assert();
+
+    let all = store.cmd().args(["export", "csv", "sheet"]).output().unwrap();
+    store.cmd().args(["remove-list", "-f", "sheet"]).assert().success();
+    let reimported = store.cmd().args(["import", "csv"]).write_stdin(all.stdout.clone()).assert();
+    let again = store.cmd().args(["export", "csv", "sheet"]).output().unwrap();
+
+    imported.success().stdout("2 tasks imported\n");
+    exported.success().stdout("Task,Done,Project\nplain,false,default\n\"multi\nline, \"\"quoted\"\" task\",true,sheet\n");
+    unmapped.success().stderr("No column for the task name\n");
+    reimported.success().stdout("1 task imported\n");
+    assert_eq!(again.stdout, all.stdout);
+}
+
+#[test]
+fn ical_import_and_export_work() {
+    let store = TempStore::new();
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    store.cmd().args(["add", "call mom; then, dad", "-p", "A", "-t", "phone", "--due", "2026-10-20"]).assert().success();
+    store.cmd().args(["add", "pay rent"]).assert().success();
+    store.cmd().args(["check", "pay rent"]).assert().success();
+
+    let exported = store.cmd().args(["export", "ical"]).output().unwrap();
+    let ics = String::from_utf8(exported.stdout).unwrap();
+    store.cmd().args(["remove-list", "-f", "default"]).assert().success();
+    let reimported = store.cmd().args(["import", "ical"]).write_stdin(ics.clone()).assert();
+    let tasks = store.cmd().arg("tasks").assert();
+
+    let thunderbird = "BEGIN:VCALENDAR\r\nPRODID:-//Mozilla.org/NONSGML Mozilla Calendar V1.1//EN\r\n\
+        VERSION:2.0\r\nBEGIN:VTODO\r\nCREATED:20261001T101500Z\r\nUID:abc-123\r\n\
+        SUMMARY:Renew the passport before the summer holidays start\\, and bo\r\n ok flights\r\n\
+        DUE;TZID=Europe/Berlin:20261105T120000\r\nPRIORITY:9\r\nSTATUS:NEEDS-ACTION\r\n\
+        END:VTODO\r\nEND:VCALENDAR\r\n";
+    let imported = store.cmd().args(["import", "ical", "--list", "inbox"]).write_stdin(thunderbird).assert();
+    let inbox = store.cmd().args(["tasks", "inbox"]).assert();
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.contains("SUMMARY:call mom\\; then\\, dad\r\nSTATUS:NEEDS-ACTION\r\nCATEGORIES:default,phone\r\nDUE;VALUE=DATE:20261020\r\nPRIORITY:1\r\n"));
+    assert!(ics.contains("SUMMARY:pay rent\r\nSTATUS:COMPLETED\r\nCOMPLETED:"));
+    reimported.success().stdout("2 tasks imported\n");
+    tasks.success().stdout("[default]\nx (A) call mom; then, dad #phone (due 2026-10-20)\n☑ pay rent\n");
+    imported.success().stdout("1 task imported\n");
+    inbox.success().stdout("x (I) Renew the passport before the summer holidays start, and book flights (due 2026-11-05)\n");
+}
+
+#[test]
+fn taskwarrior_import_and_export_work() {
+    let store = TempStore::new();
+
+    let export = r#"[
+{"id":1,"description":"Fix the bike","entry":"20261001T101500Z","modified":"20261001T101500Z","project":"home","priority":"M","status":"pending","tags":["outside"],"due":"20261104T230000Z","uuid":"3f1b2c4e-0a6d-4e8f-9b1c-2d3e4f5a6b7c","urgency":8.9},
+{"id":0,"description":"Pay rent","end":"20261002T080000Z","entry":"20261001T101600Z","project":"home","status":"completed","uuid":"7c6b5a4f-3e2d-4c1b-8f9e-6d0a4e2c1b3f"},
+{"id":0,"description":"Old idea","entry":"20261001T101700Z","status":"deleted","uuid":"0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d"},
+{"id":2,"description":"Read a book","entry":"20261001T101800Z","status":"pending","uuid":"1a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d"}
+]"#;
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    let imported = store.cmd().args(["import", "taskwarrior"]).write_stdin(export).assert();
+    let tasks = store.cmd().args(["tasks", "home"]).assert();
+    let inbox = store.cmd().args(["tasks", "default"]).assert();
+
+    let exported = store.cmd().args(["export", "taskwarrior", "home"]).output().unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&exported.stdout).unwrap();
+
+    imported.success().stdout("3 tasks imported\n");
+    inbox.success().stdout("x Read a book\n");
+    let due = chrono::DateTime::parse_from_rfc3339("2026-11-04T23:00:00Z").unwrap().with_timezone(&chrono::Local).date_naive();
+    tasks.success().stdout(format!("x (B) Fix the bike #outside (due {})\n☑ Pay rent\n", due));
+
+    assert_eq!(json[0]["description"], "Fix the bike");
+    assert_eq!(json[0]["status"], "pending");
+    assert_eq!(json[0]["project"], "home");
+    assert_eq!(json[0]["priority"], "M");
+    assert_eq!(json[0]["tags"], serde_json::json!(["outside"]));
+    assert_eq!(json[0]["entry"], "20261001T101500Z");
+    assert_eq!(json[1]["status"], "completed");
+    assert_eq!(json[1]["end"], "20261002T080000Z");
+    assert_eq!(json[1]["uuid"].as_str().unwrap().len(), 36);
+}
+
+#[test]
+fn org_round_trip_works() {
+    let store = TempStore::new();
+
+    let org = "\
+* work
+** TODO [#A] Write report :q3:review:
+DEADLINE: <2026-10-20 Tue>
+*** DONE Collect numbers
+CLOSED: [2026-10-19 Mon 09:30]
+** TODO Answer emails
+* home
+** DONE Pay rent
+** TODO Fix the bike
+";
+
+    store.cmd().args(["add-list", "default"]).assert().success();
+    let imported = store.cmd().args(["import", "org"]).write_stdin(org).assert();
+    let tasks = store.cmd().args(["tasks", "work"]).assert();
+    let home_list = store.cmd().args(["export", "org", "home"]).assert();
+    let work = store.cmd().args(["export", "org", "work"]).assert();
+
+    imported.success().stdout("5 tasks imported\n");
+    tasks.success().stdout("x (A) Write report #q3 #review (due 2026-10-20)\n☑ Collect numbers\nx Answer emails\n");
+    home_list.success().stdout("* home\n** DONE Pay rent\n** TODO Fix the bike\n");
+    work.success().stdout(org.split("* home").next().unwrap().to_string());
+}
+
+#[test]
+fn scan_works() {
+    let store = TempStore::new();
+    let project = store.path().join("my-project");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::create_dir_all(project.join("build")).unwrap();
+
+    std::fs::write(project.join(".gitignore"), "build/\n").unwrap();
+    std::fs::write(project.join("build/out.rs"), "// TODO: ignored\n").unwrap();
+    std::fs::write(project.join("src/main.rs"), "fn main() {\n    // TODO: handle errors\n    let todo = \"TODO\";\n}\n").unwrap();
+    std::fs::write(project.join("setup.py"), "x = 1  # FIXME(bob) wrong value\n/* XXX */\n").unwrap();
+
+    store.cmd_in(&project).args(["add-list", "default"]).assert().success();
+    let first = store.cmd_in(&project).arg("scan").assert();
+    let tasks = store.cmd_in(&project).args(["tasks", "my-project"]).assert();
+    store.cmd_in(&project).args(["add", "read notes (book:12)", "my-project"]).assert().success();
+
+    std::fs::write(project.join("src/main.rs"), "fn main() {\n\n    // TODO: handle errors\n    // TODO log them\n}\n").unwrap();
+    std::fs::write(project.join("setup.py"), "x = 2\n/* XXX */\n").unwrap();
+    let second = store.cmd_in(&project).arg("scan").assert();
+    let rescanned = store.cmd_in(&project).args(["tasks", "my-project"]).assert();
+    let unchanged = store.cmd_in(&project).args(["scan", "."]).assert();
+
+    first.success().stdout("3 comments found in my-project: 3 added\n");
+    tasks.success().stdout("\
+x wrong value (setup.py:1) #fixme
+x XXX (setup.py:2) #xxx
+x handle errors (src/main.rs:2) #todo
+");
+    second.success().stdout("3 comments found in my-project: 1 added, 1 moved, 1 checked\n");
+    rescanned.success().stdout("\
+☑ wrong value (setup.py:1) #fixme
+x XXX (setup.py:2) #xxx
+x handle errors (src/main.rs:3) #todo
+x read notes (book:12)
+x log them (src/main.rs:4) #todo
+");
+    unchanged.success().stdout("3 comments found in my-project, nothing changed\n");
+}
+
+#[test]
+fn project_store_works() {
+    let store = TempStore::new();
+    let home = store.path();
+    let project = home.join("project");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::create_dir_all(home.join("other/.todo")).unwrap();
+
+    store.cmd_in(home).args(["add-list", "default"]).assert().success();
+    store.cmd_in(home).args(["add", "home task"]).assert().success();
+
+    std::fs::write(project.join(".todo.json"), "").unwrap();
+    store.cmd_in(&project.join("src")).args(["add-list", "default"]).assert().success();
+    store.cmd_in(&project.join("src")).args(["add", "project task"]).assert().success();
+    let local = store.cmd_in(&project.join("src")).arg("tasks").assert();
+    let global = store.cmd_in(&project.join("src")).args(["tasks", "--global"]).assert();
+    let file = std::fs::read_to_string(project.join(".todo.json")).unwrap();
+    let mut project_files = std::fs::read_dir(&project).unwrap().map(|entry| entry.unwrap().file_name()).collect::<Vec<_>>();
+    project_files.sort();
+    let side_files = std::fs::read_dir(home.join(".todo-app/projects")).unwrap().count();
+
+    store.cmd_in(&home.join("other")).args(["add-list", "other"]).assert().success();
+    let other = store.cmd_in(&home.join("other")).arg("lists").assert();
+    let other_file = home.join("other/.todo/tasks.json").exists();
+    let home_lists = store.cmd_in(home).arg("lists").assert();
+
+    local.success().stdout("[default]\nx project task\n");
+    global.success().stdout("[default]\nx home task\n");
+    assert!(file.contains("\"project task\""));
+    // Only the tasks go to the project, the journal and the rest stay in the home store.
+    assert_eq!(project_files, [".todo.json", "src"]);
+    assert_eq!(side_files, 1);
+    other.success().stdout("other\n");
+    assert!(other_file);
+    home_lists.success().stdout("default\n");
+}